@@ -0,0 +1,275 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::dirs::system::{Environment, Provider};
+use crate::dirs::{get_component, get_default_mode, get_env_prefix, App, Kind, Mode};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Builder for [App](crate::dirs::App) which follows the naming convention of each platform.
+///
+/// The name of the application directories is derived from the qualifier, the organization and
+/// the name of the application:
+/// - on Apple platforms, this is a reverse-DNS bundle identifier (ex: `com.BlockProject3D.Editor`),
+/// - on Windows, this is `Organization\Application` (ex: `BlockProject3D\Editor`),
+/// - on any other platform, this is a lowercase slug of the application name (ex: `editor`).
+///
+/// The prefix of the environment variables which override the application directories is
+/// always derived from the name of the application (ex: `EDITOR_CACHE_DIR`).
+pub struct AppBuilder<'a> {
+    qualifier: Option<&'a str>,
+    organization: Option<&'a str>,
+    name: &'a str,
+    mode: Mode,
+    provider: Option<Arc<dyn Provider>>,
+    env: Option<Environment>,
+    modes: [Option<u32>; 7],
+    repair: bool,
+    revalidate: bool,
+    channel: Option<&'a str>,
+    version: Option<&'a str>,
+    scoped: [bool; 7],
+    sudo: bool,
+}
+
+impl<'a> AppBuilder<'a> {
+    /// Creates a new application builder.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the application.
+    ///
+    /// returns: AppBuilder
+    pub fn new(name: &'a str) -> AppBuilder<'a> {
+        AppBuilder {
+            qualifier: None,
+            organization: None,
+            name,
+            mode: Mode::User,
+            provider: None,
+            env: None,
+            modes: Kind::ALL.map(get_default_mode),
+            repair: false,
+            revalidate: false,
+            channel: None,
+            version: None,
+            scoped: Kind::ALL.map(|v| v == Kind::Cache),
+            sudo: false,
+        }
+    }
+
+    /// Sets the qualifier of the application (ex: `com` or `org`).
+    pub fn qualifier(mut self, qualifier: &'a str) -> Self {
+        self.qualifier = Some(qualifier);
+        self
+    }
+
+    /// Sets the name of the organization which develops the application.
+    pub fn organization(mut self, organization: &'a str) -> Self {
+        self.organization = Some(organization);
+        self
+    }
+
+    /// Sets whether the application runs as a system service.
+    ///
+    /// See [new_service](crate::dirs::App::new_service).
+    pub fn service(mut self, service: bool) -> Self {
+        self.mode = if service { Mode::Service } else { Mode::User };
+        self
+    }
+
+    /// Sets whether the application uses the portable layout.
+    ///
+    /// See [new_portable](crate::dirs::App::new_portable).
+    pub fn portable(mut self, portable: bool) -> Self {
+        self.mode = if portable { Mode::Portable } else { Mode::User };
+        self
+    }
+
+    /// Maps all directories of the application under the given root directory.
+    ///
+    /// See [with_root](crate::dirs::App::with_root).
+    pub fn root<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.mode = Mode::Root(root.into());
+        self
+    }
+
+    /// Sets the provider of the system directories.
+    ///
+    /// By default, the application uses the provider of the current platform.
+    pub fn provider<P: Provider + 'static>(mut self, provider: P) -> Self {
+        self.provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets the environment used to resolve the application directories.
+    ///
+    /// By default, the application uses a snapshot of the environment of the current process.
+    pub fn environment(mut self, env: Environment) -> Self {
+        self.env = Some(env);
+        self
+    }
+
+    /// Sets whether the application should resolve the directories of the user who invoked
+    /// sudo or pkexec instead of the directories of root.
+    ///
+    /// This is ignored if an environment is set with
+    /// [environment](self::AppBuilder::environment). See
+    /// [with_invoking_user](crate::dirs::system::Environment::with_invoking_user).
+    pub fn sudo(mut self, sudo: bool) -> Self {
+        self.sudo = sudo;
+        self
+    }
+
+    /// Sets the unix access mode of the given directory when it is created.
+    ///
+    /// Use None to create the directory with the process umask. The runtime directory is always
+    /// only accessible by the current user. This has no effect on non-unix platforms.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to set the access mode of.
+    /// * `mode`: the access mode (ex: 0o700).
+    ///
+    /// returns: AppBuilder
+    pub fn mode(mut self, kind: Kind, mode: Option<u32>) -> Self {
+        self.modes[kind as usize] = mode;
+        self
+    }
+
+    /// Sets whether the access mode of existing directories should be reset to the mode set with
+    /// [mode](self::AppBuilder::mode).
+    pub fn repair_permissions(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+
+    /// Sets whether directories should be checked for existence on each access and re-created,
+    /// including their parents, if they were removed.
+    pub fn revalidate(mut self, revalidate: bool) -> Self {
+        self.revalidate = revalidate;
+        self
+    }
+
+    /// Sets the release channel of the application (ex: `stable`, `beta` or `nightly`).
+    ///
+    /// Scoped directories are stored in a sub-directory named after the channel.
+    pub fn channel(mut self, channel: &'a str) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Sets the version of the application (ex: `1.2.0`).
+    ///
+    /// Scoped directories are stored in a sub-directory named after the version in a `Versions`
+    /// directory, nested in the release channel sub-directory if any.
+    pub fn version(mut self, version: &'a str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Sets whether the given directory is scoped to the release channel and version.
+    ///
+    /// By default, only the cache directory is scoped.
+    pub fn scope(mut self, kind: Kind, scoped: bool) -> Self {
+        self.scoped[kind as usize] = scoped;
+        self
+    }
+
+    /// Returns the name of the application directories on the current platform.
+    pub fn get_dir_name(&self) -> String {
+        cfg_if::cfg_if! {
+            if #[cfg(target_vendor = "apple")] {
+                self.qualifier
+                    .into_iter()
+                    .chain(self.organization)
+                    .chain(std::iter::once(self.name))
+                    .map(|v| v.trim().replace(char::is_whitespace, "-"))
+                    .collect::<Vec<_>>()
+                    .join(".")
+            } else if #[cfg(windows)] {
+                match self.organization {
+                    Some(organization) => format!("{}\\{}", organization.trim(), self.name.trim()),
+                    None => self.name.trim().into(),
+                }
+            } else {
+                self.name
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join("-")
+                    .to_lowercase()
+            }
+        }
+    }
+
+    /// Builds the application.
+    ///
+    /// returns: App
+    pub fn build(self) -> App<'a> {
+        let mut app = App::new(self.get_dir_name());
+        app.prefix = get_env_prefix(self.name);
+        app.mode = self.mode;
+        app.modes = self.modes;
+        app.repair = self.repair;
+        app.revalidate = self.revalidate;
+        app.channel = self.channel.map(get_component);
+        app.version = self.version.map(get_component);
+        app.scoped = self.scoped;
+        if let Some(provider) = self.provider {
+            app.provider = provider;
+        }
+        if let Some(env) = self.env {
+            app.env = Arc::new(env);
+        } else if self.sudo {
+            app.env = Arc::new(Environment::invoking_user());
+        }
+        app
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dirs::AppBuilder;
+
+    #[test]
+    fn dir_name() {
+        let builder = AppBuilder::new("My Editor")
+            .qualifier("com")
+            .organization("BlockProject 3D");
+        let name = builder.get_dir_name();
+        cfg_if::cfg_if! {
+            if #[cfg(target_vendor = "apple")] {
+                assert_eq!(name, "com.BlockProject-3D.My-Editor");
+            } else if #[cfg(windows)] {
+                assert_eq!(name, "BlockProject 3D\\My Editor");
+            } else {
+                assert_eq!(name, "my-editor");
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Layered discovery of configuration files.
+//!
+//! A configuration file may exist in the system-wide config directories, in the user config
+//! directory and in the [current project](crate::dirs::Project::current). Project files override
+//! user files which override system files.
+
+use crate::dirs::{App, Error, Kind, Project};
+use std::path::{Path, PathBuf};
+
+/// The scope of a configuration file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// The file applies to all users of the system.
+    System,
+
+    /// The file applies to the current user.
+    User,
+
+    /// The file applies to the current project.
+    Project,
+}
+
+/// A configuration file found by [find_config_files](crate::dirs::App::find_config_files).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigFile {
+    /// The scope of the file.
+    pub scope: Scope,
+
+    /// The path to the file.
+    pub path: PathBuf,
+}
+
+impl<'a> App<'a> {
+    /// Returns all instances of the given configuration file, from the highest precedence to the
+    /// lowest.
+    ///
+    /// The file is looked up in the project directory (`<project>/.bp3d/<name>`), then in the
+    /// [search paths](crate::dirs::App::get_search_paths) of the config directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the relative path of the configuration file.
+    ///
+    /// returns: Result<Vec<ConfigFile>, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [MissingDataDir](crate::dirs::Error::MissingDataDir) if this system doesn't have
+    /// any application writable location.
+    pub fn find_config_files<P: AsRef<Path>>(&self, name: P) -> Result<Vec<ConfigFile>, Error> {
+        self.find_config_files_in(name.as_ref(), Project::current())
+    }
+
+    fn find_config_files_in(
+        &self,
+        name: &Path,
+        project: Option<Project>,
+    ) -> Result<Vec<ConfigFile>, Error> {
+        let mut files = Vec::new();
+        if let Some(project) = project {
+            files.push(ConfigFile {
+                scope: Scope::Project,
+                path: project.get_dir().join(name),
+            });
+        }
+        for (i, path) in self.get_search_paths(Kind::Config)?.into_iter().enumerate() {
+            files.push(ConfigFile {
+                // The first search path is always the user config directory.
+                scope: if i == 0 { Scope::User } else { Scope::System },
+                path: path.join(name),
+            });
+        }
+        files.retain(|v| v.path.is_file());
+        Ok(files)
+    }
+}
+
+/// An error which occurred while merging configuration files.
+#[cfg(feature = "toml")]
+#[derive(Debug)]
+pub enum MergeError {
+    /// An io error has occurred while reading some configuration file.
+    Io {
+        /// The path of the file which couldn't be read.
+        path: PathBuf,
+
+        /// The underlying io error.
+        error: std::io::Error,
+    },
+
+    /// Some configuration file is not a valid TOML document.
+    Toml {
+        /// The path of the file which couldn't be parsed.
+        path: PathBuf,
+
+        /// The underlying parse error.
+        error: toml::de::Error,
+    },
+}
+
+#[cfg(feature = "toml")]
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::Io { path, error } => {
+                write!(f, "failed to read '{}': {}", path.display(), error)
+            }
+            MergeError::Toml { path, error } => {
+                write!(f, "failed to parse '{}': {}", path.display(), error)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+impl std::error::Error for MergeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MergeError::Io { error, .. } => Some(error),
+            MergeError::Toml { error, .. } => Some(error),
+        }
+    }
+}
+
+#[cfg(feature = "toml")]
+fn merge_table(base: &mut toml::Table, table: toml::Table) {
+    for (key, value) in table {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(table)) => merge_table(base, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Merges TOML configuration files into a single table.
+///
+/// Files must be ordered from the highest precedence to the lowest, as returned by
+/// [find_config_files](crate::dirs::App::find_config_files). Tables are merged recursively while
+/// any other value, including arrays, replaces the value of files with lower precedence.
+///
+/// # Arguments
+///
+/// * `files`: the configuration files to merge.
+///
+/// returns: Result<Table, MergeError>
+///
+/// # Errors
+///
+/// Returns a [MergeError](self::MergeError) if some file couldn't be read or parsed.
+#[cfg(feature = "toml")]
+pub fn merge_toml(files: &[ConfigFile]) -> Result<toml::Table, MergeError> {
+    let mut base = toml::Table::new();
+    for file in files.iter().rev() {
+        let content = std::fs::read_to_string(&file.path).map_err(|error| MergeError::Io {
+            path: file.path.clone(),
+            error,
+        })?;
+        let table = content.parse().map_err(|error| MergeError::Toml {
+            path: file.path.clone(),
+            error,
+        })?;
+        merge_table(&mut base, table);
+    }
+    Ok(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigFile, Scope};
+    use crate::dirs::system::{Environment, Paths};
+    use crate::dirs::tests::{StaticProvider, TempDir};
+    use crate::dirs::{AppBuilder, Project, PROJECT_DIR};
+
+    #[test]
+    fn find() {
+        let dir = TempDir::new("config_files");
+        let root = dir.path();
+        let system = root.join("system");
+        let project = root.join("project");
+        for path in [
+            root.join("user/test"),
+            system.join("etc/test"),
+            system.join("local/test"),
+            project.join(PROJECT_DIR),
+        ] {
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(path.join("app.toml"), "").unwrap();
+        }
+        let paths = Paths {
+            app_config: Some(root.join("user")),
+            app_config_dirs: vec![system.join("local"), system.join("etc")],
+            ..Default::default()
+        };
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(paths))
+            .environment(Environment::new())
+            .build();
+        let project = Project::find(&project).unwrap();
+        let files = app
+            .find_config_files_in("app.toml".as_ref(), Some(project.clone()))
+            .unwrap();
+        let expected = vec![
+            (Scope::Project, project.get_dir().join("app.toml")),
+            (Scope::User, root.join("user/test/app.toml")),
+            (Scope::System, system.join("local/test/app.toml")),
+            (Scope::System, system.join("etc/test/app.toml")),
+        ];
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(scope, path)| ConfigFile { scope, path })
+            .collect();
+        assert_eq!(files, expected);
+        std::fs::remove_file(root.join("user/test/app.toml")).unwrap();
+        let files = app.find_config_files_in("app.toml".as_ref(), None).unwrap();
+        assert_eq!(files, expected[2..]);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn merge() {
+        use super::merge_toml;
+        let dir = TempDir::new("merge");
+        let root = dir.path();
+        std::fs::create_dir_all(root).unwrap();
+        std::fs::write(root.join("user.toml"), "[window]\nwidth = 800\n").unwrap();
+        std::fs::write(
+            root.join("system.toml"),
+            "theme = \"dark\"\n[window]\nwidth = 640\nheight = 480\n",
+        )
+        .unwrap();
+        let files = [
+            ConfigFile {
+                scope: Scope::User,
+                path: root.join("user.toml"),
+            },
+            ConfigFile {
+                scope: Scope::System,
+                path: root.join("system.toml"),
+            },
+        ];
+        let table = merge_toml(&files).unwrap();
+        assert_eq!(table["theme"].as_str(), Some("dark"));
+        assert_eq!(table["window"]["width"].as_integer(), Some(800));
+        assert_eq!(table["window"]["height"].as_integer(), Some(480));
+    }
+}
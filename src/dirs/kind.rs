@@ -0,0 +1,112 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::fmt::{Display, Formatter};
+
+/// Represents an application directory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// The application data directory.
+    Data,
+
+    /// The application cache directory.
+    Cache,
+
+    /// The application public documents directory.
+    Documents,
+
+    /// The application logs directory.
+    Logs,
+
+    /// The application persistent state directory.
+    State,
+
+    /// The application runtime directory.
+    Runtime,
+
+    /// The application config directory.
+    Config,
+}
+
+impl Kind {
+    /// All application directories.
+    pub const ALL: [Kind; 7] = [
+        Kind::Data,
+        Kind::Cache,
+        Kind::Documents,
+        Kind::Logs,
+        Kind::State,
+        Kind::Runtime,
+        Kind::Config,
+    ];
+
+    /// Returns the name of this directory (ex: `data`).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Data => "data",
+            Kind::Cache => "cache",
+            Kind::Documents => "documents",
+            Kind::Logs => "logs",
+            Kind::State => "state",
+            Kind::Runtime => "runtime",
+            Kind::Config => "config",
+        }
+    }
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Represents where an application directory was resolved from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// The directory was overridden by an environment variable.
+    Environment,
+
+    /// The directory was provided by the system.
+    System,
+
+    /// The directory was provided by the service manager or is a standard service location.
+    Service,
+
+    /// The directory is stored next to the application executable.
+    Portable,
+
+    /// The directory is mapped under the root given to [with_root](crate::dirs::App::with_root).
+    Root,
+
+    /// The system has no such directory, the directory is stored inside another application
+    /// directory.
+    Fallback(Kind),
+
+    /// The system has no such directory, the directory is stored in the system temporary directory.
+    Temporary,
+}
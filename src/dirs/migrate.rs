@@ -0,0 +1,146 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Migration of application files from legacy locations.
+//!
+//! Entries are moved with a rename whenever possible which is atomic. When the legacy location
+//! is on a different file system, entries are first copied to a temporary name in the destination
+//! and then renamed, so that a partially copied entry never appears at its final location.
+//! Entries already present at the destination are never overwritten which makes migrations
+//! idempotent: directories are merged and conflicting files are left in the legacy location.
+
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+/// Reports the result of a migration.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Migration {
+    /// The list of moved entries as (legacy path, new path).
+    pub moved: Vec<(PathBuf, PathBuf)>,
+
+    /// The list of legacy entries left in place because they already exist at the destination.
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Migration {
+    /// Returns true if nothing was found to migrate.
+    pub fn is_empty(&self) -> bool {
+        self.moved.is_empty() && self.skipped.is_empty()
+    }
+}
+
+fn copy_all(from: &Path, to: &Path) -> Result<()> {
+    let metadata = std::fs::symlink_metadata(from)?;
+    if metadata.is_dir() {
+        std::fs::create_dir(to)?;
+        std::fs::set_permissions(to, metadata.permissions())?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_all(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+    #[cfg(unix)]
+    if metadata.file_type().is_symlink() {
+        return std::os::unix::fs::symlink(std::fs::read_link(from)?, to);
+    }
+    std::fs::copy(from, to).map(|_| ())
+}
+
+fn remove_all(path: &Path) -> Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Returns true if the given error was caused by a rename across file systems.
+fn is_cross_device(error: &std::io::Error) -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            error.raw_os_error() == Some(libc::EXDEV)
+        } else if #[cfg(windows)] {
+            // ERROR_NOT_SAME_DEVICE
+            error.raw_os_error() == Some(17)
+        } else {
+            let _ = error;
+            false
+        }
+    }
+}
+
+/// Moves a single entry, falling back to a copy when the entry is on another file system.
+fn move_entry(from: &Path, to: &Path) -> Result<()> {
+    match std::fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if !is_cross_device(&e) => return Err(e),
+        Err(_) => (),
+    }
+    let mut name = std::ffi::OsString::from(".");
+    name.push(to.file_name().unwrap_or_default());
+    name.push(".migrating");
+    let tmp = to.with_file_name(name);
+    // Remove left-overs of an interrupted migration.
+    if std::fs::symlink_metadata(&tmp).is_ok() {
+        remove_all(&tmp)?;
+    }
+    copy_all(from, &tmp)?;
+    std::fs::rename(&tmp, to)?;
+    remove_all(from)
+}
+
+/// Merges the content of the legacy directory `from` into the directory `to`.
+///
+/// The legacy directory is removed once empty. On failure, returns the path of the entry which
+/// couldn't be migrated along with the error.
+pub fn merge(
+    from: &Path,
+    to: &Path,
+    report: &mut Migration,
+) -> std::result::Result<(), (PathBuf, std::io::Error)> {
+    let entries = std::fs::read_dir(from).map_err(|e| (from.into(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| (from.into(), e))?;
+        let src = entry.path();
+        let dst = to.join(entry.file_name());
+        match std::fs::symlink_metadata(&dst) {
+            Err(_) => {
+                move_entry(&src, &dst).map_err(|e| (src.clone(), e))?;
+                report.moved.push((src, dst));
+            }
+            Ok(metadata) if metadata.is_dir() && src.is_dir() && !src.is_symlink() => {
+                merge(&src, &dst, report)?
+            }
+            Ok(_) => report.skipped.push(src),
+        }
+    }
+    // Conflicting entries are kept in the legacy directory, so it may not be empty.
+    let _ = std::fs::remove_dir(from);
+    Ok(())
+}
@@ -26,27 +26,153 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::dirs::system::get_app_bundled_asset;
+use crate::dirs::system::{get_app_bundled_asset, Environment, Paths, Provider};
 use once_cell::sync::OnceCell;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+mod builder;
+mod config;
+mod kind;
+mod migrate;
+mod profile;
+mod project;
+mod service;
 pub mod system;
 
+pub use builder::AppBuilder;
+#[cfg(feature = "toml")]
+pub use config::{merge_toml, MergeError};
+pub use config::{ConfigFile, Scope};
+pub use kind::{Kind, Source};
+pub use migrate::Migration;
+pub use project::{Project, PROJECT_DIR, PROJECT_FILE};
+
+/// The operation which failed on some application directory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// The directory couldn't be created.
+    Create,
+
+    /// Some legacy entry couldn't be migrated to the directory.
+    Migrate,
+
+    /// Some profile in the directory couldn't be listed, created, renamed or deleted.
+    Profile,
+
+    /// Old versions of the directory couldn't be listed or removed.
+    Purge,
+}
+
 /// Represents all possible errors when requesting app directories.
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// The system is missing an application data directory.
     MissingDataDir,
 
-    /// An io error has occurred while created some directory.
-    Io(std::io::Error),
+    /// An io error has occurred while operating on some directory.
+    Io {
+        /// The operation which failed.
+        operation: Operation,
+
+        /// The directory the operation applies to.
+        kind: Kind,
+
+        /// The path which caused the error.
+        path: PathBuf,
+
+        /// The underlying io error.
+        error: std::io::Error,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::MissingDataDir => f.write_str("missing application data directory"),
+            Error::Io {
+                operation,
+                kind,
+                path,
+                error,
+            } => {
+                let path = path.display();
+                match operation {
+                    Operation::Create => {
+                        write!(
+                            f,
+                            "failed to create {} directory '{}': {}",
+                            kind, path, error
+                        )
+                    }
+                    Operation::Migrate => {
+                        write!(
+                            f,
+                            "failed to migrate '{}' to {} directory: {}",
+                            path, kind, error
+                        )
+                    }
+                    Operation::Profile => write!(
+                        f,
+                        "failed to update profile in {} directory '{}': {}",
+                        kind, path, error
+                    ),
+                    Operation::Purge => write!(
+                        f,
+                        "failed to purge old versions of {} directory '{}': {}",
+                        kind, path, error
+                    ),
+                }
+            }
+        }
+    }
 }
 
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Self::Io(err)
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::MissingDataDir => None,
+            Error::Io { error, .. } => Some(error),
+        }
     }
 }
 
+/// The name of the marker file which enables the portable layout when placed next to the
+/// application executable.
+pub const PORTABLE_MARKER: &str = "portable.txt";
+
+/// The name of the directory next to the application executable which stores all directories
+/// of a portable application.
+pub const PORTABLE_DIR: &str = "UserData";
+
+/// The name of the directory which stores the profiles of an application, nested in its data,
+/// config and documents directories.
+pub const PROFILES_DIR: &str = "Profiles";
+
+/// The name of the directory which stores the directories of each version of an application,
+/// nested in its release channel directory if any.
+pub const VERSIONS_DIR: &str = "Versions";
+
+#[derive(Clone, PartialEq, Eq)]
+enum Mode {
+    /// Directories are provided by the system unless a portable marker is found.
+    User,
+
+    /// Directories are provided by the service manager.
+    Service,
+
+    /// Directories are stored next to the application executable.
+    Portable,
+
+    /// Directories are mapped under the given root directory.
+    Root(PathBuf),
+}
+
 /// Represents the application's directories.
 ///
 /// Main entry point to obtain any directory for your application.
@@ -54,32 +180,610 @@ impl From<std::io::Error> for Error {
 /// These APIs will fail as last resort. If they fail it usually means the system has a problem.
 /// The system may also include specific configuration to break applications on purpose,
 /// in which case these APIs will also fail.
+///
+/// Any directory can be overridden with an environment variable named
+/// `<APPNAME>_<KIND>_DIR` where `<APPNAME>` is the name of the application in upper case with
+/// any character other than letters and digits replaced by `_` and `<KIND>` is the
+/// [name](self::Kind::name) of the directory in upper case (ex: `MY_APP_CACHE_DIR`). Overrides must
+/// be absolute paths, otherwise they are ignored.
+///
+/// On unix platforms, the data, config, state and runtime directories are created only
+/// accessible by the current user. Use [AppBuilder](self::AppBuilder) to change the access mode of
+/// the other directories.
+///
+/// Directories may be scoped to a release channel and version with
+/// [channel](self::AppBuilder::channel) and [version](self::AppBuilder::version), in which case
+/// they are stored in `<channel>/Versions/<version>` sub-directories. By default, only the cache
+/// directory is scoped so that data and settings are shared by all versions.
+///
+/// Directory paths are cached after their first access. Use
+/// [revalidate](self::AppBuilder::revalidate) to re-create directories removed while the
+/// application is running.
 pub struct App<'a> {
-    name: &'a str,
-    data: OnceCell<PathBuf>,
-    cache: OnceCell<PathBuf>,
-    docs: OnceCell<PathBuf>,
-    logs: OnceCell<PathBuf>,
-    config: OnceCell<PathBuf>,
+    name: Cow<'a, str>,
+    prefix: String,
+    mode: Mode,
+    provider: Arc<dyn Provider>,
+    env: Arc<Environment>,
+    paths: OnceCell<Paths>,
+    portable: OnceCell<Option<PathBuf>>,
+    modes: [Option<u32>; 7],
+    repair: bool,
+    revalidate: bool,
+    channel: Option<String>,
+    version: Option<String>,
+    scoped: [bool; 7],
+    profile: Option<String>,
+    dirs: [OnceCell<(PathBuf, Source)>; 7],
 }
 
 impl<'a> App<'a> {
     /// Creates a new application.
     ///
+    /// The name is used as-is for the application directories on all platforms. Use
+    /// [AppBuilder](self::AppBuilder) to follow the naming convention of each platform.
+    ///
     /// # Arguments
     ///
     /// * `name`: the name of the application.
     ///
     /// returns: App
-    pub fn new(name: &'a str) -> App<'a> {
+    pub fn new<T: Into<Cow<'a, str>>>(name: T) -> App<'a> {
+        let name = name.into();
         App {
+            prefix: get_env_prefix(&name),
             name,
-            data: OnceCell::new(),
-            cache: OnceCell::new(),
-            docs: OnceCell::new(),
-            logs: OnceCell::new(),
-            config: OnceCell::new(),
+            mode: Mode::User,
+            provider: Arc::new(system::System),
+            env: Arc::new(Environment::current()),
+            paths: OnceCell::new(),
+            portable: OnceCell::new(),
+            modes: Kind::ALL.map(get_default_mode),
+            repair: false,
+            revalidate: false,
+            channel: None,
+            version: None,
+            scoped: Kind::ALL.map(|v| v == Kind::Cache),
+            profile: None,
+            dirs: Default::default(),
+        }
+    }
+
+    /// Creates a new application running as a system service.
+    ///
+    /// Directories are taken from the variables systemd passes to services
+    /// (STATE_DIRECTORY, CACHE_DIRECTORY, LOGS_DIRECTORY, CONFIGURATION_DIRECTORY and
+    /// RUNTIME_DIRECTORY). When these variables are absent, directories fallback to the
+    /// standard system locations (/var/lib, /var/cache, /var/log, /etc and /run).
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the application.
+    ///
+    /// returns: App
+    pub fn new_service<T: Into<Cow<'a, str>>>(name: T) -> App<'a> {
+        App {
+            mode: Mode::Service,
+            ..App::new(name)
+        }
+    }
+
+    /// Creates a new portable application.
+    ///
+    /// All directories, except the runtime directory, are stored in a directory named
+    /// [PORTABLE_DIR](self::PORTABLE_DIR) next to the application executable. An application
+    /// created with [new](self::App::new) also switches to this layout when a file named
+    /// [PORTABLE_MARKER](self::PORTABLE_MARKER) is found next to the application executable.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the application.
+    ///
+    /// returns: App
+    pub fn new_portable<T: Into<Cow<'a, str>>>(name: T) -> App<'a> {
+        App {
+            mode: Mode::Portable,
+            ..App::new(name)
+        }
+    }
+
+    /// Creates a new application with all directories mapped under the given root directory.
+    ///
+    /// Each system directory is mapped to a sub-directory of the root named after the
+    /// [name](self::Kind::name) of the directory (ex: `<root>/cache/<name>`). Environment
+    /// overrides are ignored, which makes this constructor suitable for tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the application.
+    /// * `root`: the root directory.
+    ///
+    /// returns: App
+    pub fn with_root<T: Into<Cow<'a, str>>, P: Into<PathBuf>>(name: T, root: P) -> App<'a> {
+        App {
+            mode: Mode::Root(root.into()),
+            ..App::new(name)
+        }
+    }
+
+    /// Converts this application into an application which owns its name.
+    ///
+    /// Already resolved directories are preserved.
+    pub fn into_owned(self) -> App<'static> {
+        App {
+            name: Cow::Owned(self.name.into_owned()),
+            prefix: self.prefix,
+            mode: self.mode,
+            provider: self.provider,
+            env: self.env,
+            paths: self.paths,
+            portable: self.portable,
+            modes: self.modes,
+            repair: self.repair,
+            revalidate: self.revalidate,
+            channel: self.channel,
+            version: self.version,
+            scoped: self.scoped,
+            profile: self.profile,
+            dirs: self.dirs,
+        }
+    }
+
+    /// Returns true if this application uses the portable layout.
+    pub fn is_portable(&self) -> bool {
+        self.get_portable_root().is_some()
+    }
+
+    fn get_paths(&self) -> &Paths {
+        self.paths
+            .get_or_init(|| self.provider.get_paths(&self.env))
+    }
+
+    fn get_portable_root(&self) -> Option<&Path> {
+        self.portable
+            .get_or_init(|| {
+                if self.mode != Mode::User && self.mode != Mode::Portable {
+                    return None;
+                }
+                let dir = self.get_paths().app_executable_dir.as_ref()?;
+                if self.mode == Mode::Portable || dir.join(PORTABLE_MARKER).is_file() {
+                    Some(dir.join(PORTABLE_DIR))
+                } else {
+                    None
+                }
+            })
+            .as_deref()
+    }
+
+    /// Returns a system directory or None if this application doesn't use system directories,
+    /// in which case directories are stored in the application data directory.
+    fn get_system_dir(&self, kind: Kind) -> Option<(PathBuf, Source)> {
+        match &self.mode {
+            Mode::Service => return None,
+            Mode::Root(root) => {
+                return match kind {
+                    Kind::Documents | Kind::Logs => None,
+                    _ => Some((root.join(kind.name()), Source::Root)),
+                }
+            }
+            // Runtime files do not belong to the portable storage.
+            _ if kind != Kind::Runtime && self.get_portable_root().is_some() => return None,
+            _ => (),
+        }
+        let paths = self.get_paths();
+        let dir = match kind {
+            Kind::Data => &paths.app_data,
+            Kind::Cache => &paths.app_cache,
+            Kind::Documents => &paths.app_documents,
+            Kind::Logs => &paths.app_logs,
+            Kind::State => &paths.app_state,
+            Kind::Runtime => &paths.app_runtime,
+            Kind::Config => &paths.app_config,
+        };
+        dir.clone().map(|v| (v, Source::System))
+    }
+
+    fn get_override(&self, kind: Kind) -> Option<PathBuf> {
+        if let Mode::Root(_) = self.mode {
+            return None;
+        }
+        let var = format!("{}_{}_DIR", self.prefix, kind.name().to_uppercase());
+        self.env
+            .get_var(var)
+            .map(PathBuf::from)
+            .filter(|v| v.is_absolute())
+    }
+
+    /// Returns the path to a directory nested in another application directory.
+    fn resolve_nested(&self, parent: Kind, name: &str) -> Result<(PathBuf, Source), Error> {
+        let (path, _) = self.resolve_base(parent)?;
+        Ok((path.join(name), Source::Fallback(parent)))
+    }
+
+    fn resolve_system(
+        &self,
+        kind: Kind,
+        parent: Kind,
+        name: &str,
+    ) -> Result<(PathBuf, Source), Error> {
+        match self.get_system_dir(kind) {
+            Some((dir, source)) => Ok((dir.join(&*self.name), source)),
+            None => self.resolve_nested(parent, name),
+        }
+    }
+
+    /// Resolves a directory before scoping it to the release channel and version.
+    fn resolve_base(&self, kind: Kind) -> Result<(PathBuf, Source), Error> {
+        if let Some(path) = self.get_override(kind) {
+            return Ok((path, Source::Environment));
+        }
+        if self.mode == Mode::Service {
+            if let Some(path) = service::get_dir(&self.env, kind, &self.name) {
+                return Ok((path, Source::Service));
+            }
+        }
+        match kind {
+            Kind::Data => match self.get_portable_root() {
+                Some(root) => Ok((root.into(), Source::Portable)),
+                // Never fallback to system directories when portable mode was requested.
+                None if self.mode == Mode::Portable => Err(Error::MissingDataDir),
+                None => self
+                    .get_system_dir(Kind::Data)
+                    .map(|(dir, source)| (dir.join(&*self.name), source))
+                    .ok_or(Error::MissingDataDir),
+            },
+            Kind::Cache => self.resolve_system(Kind::Cache, Kind::Data, "Cache"),
+            // If this is OK then we must be running from a sandboxed system
+            // where the app has it's own public documents folder, otherwise
+            // create a "public" Documents directory inside the application data directory.
+            Kind::Documents => match self.get_system_dir(Kind::Documents) {
+                Some(docs) => Ok(docs),
+                None => self.resolve_nested(Kind::Data, "Documents"),
+            },
+            // Logs should be public and not contain any sensitive information, so store that in
+            // the app's public documents unless the system has a dedicated state directory.
+            Kind::Logs => match self.get_system_dir(Kind::Logs) {
+                Some((logs, source)) => Ok((logs.join(&*self.name), source)),
+                None => match self.get_system_dir(Kind::State) {
+                    Some(_) => self.resolve_nested(Kind::State, "Logs"),
+                    None => self.resolve_nested(Kind::Documents, "Logs"),
+                },
+            },
+            Kind::State => self.resolve_system(Kind::State, Kind::Data, "State"),
+            Kind::Runtime => match self.get_system_dir(Kind::Runtime) {
+                Some((runtime, source)) => Ok((runtime.join(&*self.name), source)),
+                None => {
+                    let owner = self.env.get_owner();
+                    Ok((get_runtime_fallback(&self.name, owner), Source::Temporary))
+                }
+            },
+            Kind::Config => self.resolve_system(Kind::Config, Kind::Data, "Config"),
+        }
+    }
+
+    fn resolve_dir(&self, kind: Kind) -> Result<(PathBuf, Source), Error> {
+        let (mut path, source) = self.resolve_base(kind)?;
+        if self.scoped[kind as usize] {
+            path.extend(&self.channel);
+            if let Some(version) = &self.version {
+                path.push(VERSIONS_DIR);
+                path.push(version);
+            }
+        }
+        if let Some(profile) = &self.profile {
+            if profile::KINDS.contains(&kind) {
+                path.push(PROFILES_DIR);
+                path.push(profile);
+            }
+        }
+        Ok((path, source))
+    }
+
+    /// Returns the directory containing all versions of the given directory or None if the
+    /// directory isn't scoped to a version.
+    fn get_versions_dir(&self, kind: Kind) -> Result<Option<PathBuf>, Error> {
+        if !self.scoped[kind as usize] || self.version.is_none() {
+            return Ok(None);
+        }
+        let (mut path, _) = self.resolve_base(kind)?;
+        path.extend(&self.channel);
+        path.push(VERSIONS_DIR);
+        Ok(Some(path))
+    }
+
+    /// Lists the directories left by older versions of this application in the same release
+    /// channel.
+    ///
+    /// Versions are compared component by component (`1.10` is newer than `1.9`) and a
+    /// pre-release (`2.0-beta`) is older than its release. Directories of newer versions are
+    /// never listed, so an older build can't remove the files of a newer one. Returns an empty
+    /// list if the application has no version or the given directory isn't scoped to the
+    /// version.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to list old versions of.
+    ///
+    /// returns: Result<Vec<PathBuf>, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the directory couldn't be resolved or listed.
+    pub fn list_old_versions(&self, kind: Kind) -> Result<Vec<PathBuf>, Error> {
+        let dir = match self.get_versions_dir(kind)? {
+            Some(v) => v,
+            None => return Ok(Vec::new()),
+        };
+        let to_error = |path: &Path, error| Error::Io {
+            operation: Operation::Purge,
+            kind,
+            path: path.into(),
+            error,
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(to_error(&dir, e)),
+        };
+        let current = match &self.version {
+            Some(v) => v,
+            None => return Ok(Vec::new()),
+        };
+        let mut versions = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|e| to_error(&dir, e))?;
+            let is_old = entry
+                .file_name()
+                .to_str()
+                .is_some_and(|v| compare_versions(v, current) == Ordering::Less);
+            if is_old && entry.file_type().map_err(|e| to_error(&dir, e))?.is_dir() {
+                versions.push(entry.path());
+            }
+        }
+        versions.sort();
+        Ok(versions)
+    }
+
+    /// Removes the directories left by older versions of this application in the same release
+    /// channel.
+    ///
+    /// See [list_old_versions](self::App::list_old_versions).
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to purge old versions of.
+    ///
+    /// returns: Result<Vec<PathBuf>, Error> the list of removed directories.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the directory couldn't be resolved or some old version
+    /// couldn't be removed.
+    pub fn purge_old_versions(&self, kind: Kind) -> Result<Vec<PathBuf>, Error> {
+        let versions = self.list_old_versions(kind)?;
+        for path in &versions {
+            std::fs::remove_dir_all(path).map_err(|error| Error::Io {
+                operation: Operation::Purge,
+                kind,
+                path: path.clone(),
+                error,
+            })?;
+        }
+        Ok(versions)
+    }
+
+    fn create_dir(&self, kind: Kind, path: &Path, source: Source) -> Result<(), Error> {
+        let owner = self.env.get_owner();
+        let res = match (kind, source) {
+            // Runtime directories must only be accessible by the current user, except if
+            // provided by the service manager which controls its access mode.
+            (Kind::Runtime, Source::System | Source::Root | Source::Temporary) => {
+                create_private_dir(path, owner)
+            }
+            // The service manager controls the access mode of the directories it created, standard
+            // service locations are created by this application.
+            (_, Source::Service) if service::is_managed(&self.env, kind) => {
+                create_dir(path, None, false, owner)
+            }
+            _ => create_dir(path, self.modes[kind as usize], self.repair, owner),
+        };
+        res.map_err(|error| Error::Io {
+            operation: Operation::Create,
+            kind,
+            path: path.into(),
+            error,
+        })
+    }
+
+    /// Creates the directory of the given kind, after the directory it is nested in, if any, so
+    /// that the parent gets its own access mode rather than being created along with the child.
+    fn create_nested_dir(&self, kind: Kind, path: &Path, source: Source) -> Result<(), Error> {
+        if let Source::Fallback(parent) = source {
+            self.get_dir(parent)?;
+        }
+        self.create_dir(kind, path, source)
+    }
+
+    fn get_dir(&self, kind: Kind) -> Result<&(PathBuf, Source), Error> {
+        let dir = self.dirs[kind as usize].get_or_try_init(|| {
+            let (path, source) = self.resolve_dir(kind)?;
+            self.create_nested_dir(kind, &path, source)?;
+            Ok((path, source))
+        })?;
+        if self.revalidate && !dir.0.is_dir() {
+            // The directory was removed since it was first created.
+            self.create_nested_dir(kind, &dir.0, dir.1)?;
+        }
+        Ok(dir)
+    }
+
+    /// Returns the path the given application directory would have, without creating it.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to resolve.
+    ///
+    /// returns: Result<PathBuf, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [MissingDataDir](self::Error::MissingDataDir) if this system doesn't have any
+    /// application writable location.
+    pub fn resolve(&self, kind: Kind) -> Result<PathBuf, Error> {
+        match self.dirs[kind as usize].get() {
+            Some((path, _)) => Ok(path.clone()),
+            None => self.resolve_dir(kind).map(|(path, _)| path),
+        }
+    }
+
+    /// Moves the content of legacy directories into the given application directory.
+    ///
+    /// Legacy directories which don't exist or contain the application directory are ignored
+    /// and entries which already exist in the application directory are left in place, so it is
+    /// safe to call this function on every start. Legacy directories are removed once all their
+    /// entries are migrated.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the application directory to migrate to.
+    /// * `legacy`: the list of legacy directories (ex: `~/.bp3d-editor`).
+    ///
+    /// returns: Result<Migration, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the application directory couldn't be created or some
+    /// entry couldn't be migrated. Entries migrated before the error are not rolled back.
+    pub fn migrate<P: AsRef<Path>>(&self, kind: Kind, legacy: &[P]) -> Result<Migration, Error> {
+        let dir = self.get(kind)?;
+        let mut report = Migration::default();
+        for path in legacy {
+            let path = path.as_ref();
+            // A legacy directory containing the application directory would be moved into itself.
+            if !path.is_dir() || dir.starts_with(path) {
+                continue;
+            }
+            migrate::merge(path, dir, &mut report).map_err(|(path, error)| Error::Io {
+                operation: Operation::Migrate,
+                kind,
+                path,
+                error,
+            })?;
+        }
+        Ok(report)
+    }
+
+    /// Returns the list of directories to search for files of the given kind, in order of
+    /// preference.
+    ///
+    /// The first directory is always the application directory, followed by the system-wide
+    /// directories of the application for data and config (ex: `/usr/share/<app>` and
+    /// `/etc/xdg/<app>` on Linux). System-wide directories are not searched by portable
+    /// applications or applications mapped under a root directory. Directories are not created.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the kind of directories to search.
+    ///
+    /// returns: Result<Vec<PathBuf>, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [MissingDataDir](self::Error::MissingDataDir) if this system doesn't have any
+    /// application writable location.
+    pub fn get_search_paths(&self, kind: Kind) -> Result<Vec<PathBuf>, Error> {
+        let mut paths = vec![self.resolve(kind)?];
+        if matches!(self.mode, Mode::Root(_)) || self.get_portable_root().is_some() {
+            return Ok(paths);
+        }
+        let system = self.get_paths();
+        let dirs = match kind {
+            Kind::Data => &system.app_data_dirs,
+            Kind::Config => &system.app_config_dirs,
+            _ => return Ok(paths),
+        };
+        paths.extend(dirs.iter().map(|v| v.join(&*self.name)));
+        Ok(paths)
+    }
+
+    /// Returns the first file with the given name in the search paths of the given kind.
+    ///
+    /// See [get_search_paths](self::App::get_search_paths).
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the kind of directories to search.
+    /// * `name`: the relative path of the file to look for.
+    ///
+    /// returns: Result<Option<PathBuf>, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns a [MissingDataDir](self::Error::MissingDataDir) if this system doesn't have any
+    /// application writable location.
+    pub fn find_file<P: AsRef<Path>>(&self, kind: Kind, name: P) -> Result<Option<PathBuf>, Error> {
+        let paths = self.get_search_paths(kind)?;
+        Ok(paths
+            .into_iter()
+            .map(|v| v.join(name.as_ref()))
+            .find(|v| v.is_file()))
+    }
+
+    /// Returns the first config file with the given name.
+    ///
+    /// See [find_file](self::App::find_file).
+    pub fn find_config_file<P: AsRef<Path>>(&self, name: P) -> Result<Option<PathBuf>, Error> {
+        self.find_file(Kind::Config, name)
+    }
+
+    /// Returns the first data file with the given name.
+    ///
+    /// See [find_file](self::App::find_file).
+    pub fn find_data_file<P: AsRef<Path>>(&self, name: P) -> Result<Option<PathBuf>, Error> {
+        self.find_file(Kind::Data, name)
+    }
+
+    /// Creates all application directories.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if some directory couldn't be resolved or created.
+    pub fn ensure(&self) -> Result<(), Error> {
+        for kind in Kind::ALL {
+            self.get(kind)?;
         }
+        Ok(())
+    }
+
+    /// Returns the path to the given application directory, creating it if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to get.
+    ///
+    /// returns: Result<&Path, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the directory couldn't be resolved or created.
+    pub fn get(&self, kind: Kind) -> Result<&Path, Error> {
+        self.get_dir(kind).map(|(path, _)| path.as_ref())
+    }
+
+    /// Returns where the given application directory was resolved from, creating the directory
+    /// if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to get the source of.
+    ///
+    /// returns: Result<Source, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the directory couldn't be resolved or created.
+    pub fn get_source(&self, kind: Kind) -> Result<Source, Error> {
+        self.get_dir(kind).map(|(_, source)| *source)
     }
 
     /// Returns the path to this application's files.
@@ -93,17 +797,7 @@ impl<'a> App<'a> {
     ///
     /// Returns an [Io](self::Error::Io) if some directory couldn't be created.
     pub fn get_data(&self) -> Result<&Path, Error> {
-        self.data
-            .get_or_try_init(|| {
-                let data = system::get_app_data()
-                    .ok_or(Error::MissingDataDir)?
-                    .join(self.name);
-                if !data.is_dir() {
-                    std::fs::create_dir_all(&data)?;
-                }
-                Ok(data)
-            })
-            .map(|v| v.as_ref())
+        self.get(Kind::Data)
     }
 
     /// Returns the path to this application's cache.
@@ -114,18 +808,7 @@ impl<'a> App<'a> {
     ///
     /// Returns an [Io](self::Error::Io) if some directory couldn't be created.
     pub fn get_cache(&self) -> Result<&Path, Error> {
-        self.cache
-            .get_or_try_init(|| {
-                let cache = match system::get_app_cache() {
-                    None => self.get_data()?.join("Cache"),
-                    Some(cache) => cache.join(self.name),
-                };
-                if !cache.is_dir() {
-                    std::fs::create_dir(&cache)?;
-                }
-                Ok(cache)
-            })
-            .map(|v| v.as_ref())
+        self.get(Kind::Cache)
     }
 
     /// Returns the path to this application's public documents.
@@ -136,21 +819,7 @@ impl<'a> App<'a> {
     ///
     /// Returns an [Io](self::Error::Io) if some directory couldn't be created.
     pub fn get_documents(&self) -> Result<&Path, Error> {
-        // If this is OK then we must be running from a sandboxed system
-        // where the app has it's own public documents folder, otherwise
-        // create a "public" Documents directory inside the application data directory.
-        self.docs
-            .get_or_try_init(|| match system::get_app_documents() {
-                Some(docs) => Ok(docs),
-                None => {
-                    let docs = self.get_data()?.join("Documents");
-                    if !docs.is_dir() {
-                        std::fs::create_dir(&docs)?;
-                    }
-                    Ok(docs)
-                }
-            })
-            .map(|v| v.as_ref())
+        self.get(Kind::Documents)
     }
 
     /// Returns the path to this application's logs.
@@ -161,20 +830,33 @@ impl<'a> App<'a> {
     ///
     /// Returns an [Io](self::Error::Io) if some directory couldn't be created.
     pub fn get_logs(&self) -> Result<&Path, Error> {
-        // Logs should be public and not contain any sensitive information, so store that in
-        // the app's public documents.
-        self.logs
-            .get_or_try_init(|| {
-                let logs = match system::get_app_logs() {
-                    None => self.get_documents()?.join("Logs"),
-                    Some(logs) => logs.join(self.name),
-                };
-                if !logs.is_dir() {
-                    std::fs::create_dir(&logs)?;
-                }
-                Ok(logs)
-            })
-            .map(|v| v.as_ref())
+        self.get(Kind::Logs)
+    }
+
+    /// Returns the path to this application's persistent state.
+    ///
+    /// Use this directory to store data which should persist across application restarts but
+    /// is not important enough to be stored in the data directory (ex: history, window layout).
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Io](self::Error::Io) if some directory couldn't be created.
+    pub fn get_state(&self) -> Result<&Path, Error> {
+        self.get(Kind::State)
+    }
+
+    /// Returns the path to this application's runtime files.
+    ///
+    /// Use this directory to store non-essential runtime files such as sockets or pid files.
+    /// The directory is only accessible by the current user. If the system has no runtime
+    /// directory, a private directory in the system temporary directory is used instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Io](self::Error::Io) if some directory couldn't be created or if the
+    /// directory already exists and isn't private to the current user.
+    pub fn get_runtime(&self) -> Result<&Path, Error> {
+        self.get(Kind::Runtime)
     }
 
     /// Returns the path to this application's config.
@@ -186,30 +868,243 @@ impl<'a> App<'a> {
     ///
     /// Returns an [Io](self::Error::Io) if some directory couldn't be created.
     pub fn get_config(&self) -> Result<&Path, Error> {
-        self.config
-            .get_or_try_init(|| {
-                let config = match system::get_app_config() {
-                    None => self.get_data()?.join("Config"),
-                    Some(config) => config.join(self.name),
-                };
-                if !config.is_dir() {
-                    std::fs::create_dir(&config)?;
-                }
-                Ok(config)
-            })
-            .map(|v| v.as_ref())
+        self.get(Kind::Config)
+    }
+}
+
+static GLOBAL: OnceCell<App<'static>> = OnceCell::new();
+
+impl App<'static> {
+    /// Sets the process-wide application.
+    ///
+    /// Directories resolved through the process-wide application are shared by all its users.
+    ///
+    /// # Arguments
+    ///
+    /// * `app`: the application.
+    ///
+    /// returns: bool
+    ///
+    /// Returns false if the process-wide application was already set, in which case the given
+    /// application is dropped.
+    pub fn init_global(app: App<'static>) -> bool {
+        GLOBAL.set(app).is_ok()
+    }
+
+    /// Returns the process-wide application or None if [init_global](self::App::init_global)
+    /// wasn't called.
+    pub fn global() -> Option<&'static App<'static>> {
+        GLOBAL.get()
     }
 }
 
 impl<'a> Clone for App<'a> {
     fn clone(&self) -> Self {
         App {
-            name: self.name,
-            data: self.data.clone(),
-            cache: self.cache.clone(),
-            docs: self.docs.clone(),
-            logs: self.logs.clone(),
-            config: self.config.clone(),
+            name: self.name.clone(),
+            prefix: self.prefix.clone(),
+            mode: self.mode.clone(),
+            provider: self.provider.clone(),
+            env: self.env.clone(),
+            paths: self.paths.clone(),
+            portable: self.portable.clone(),
+            modes: self.modes,
+            repair: self.repair,
+            revalidate: self.revalidate,
+            channel: self.channel.clone(),
+            version: self.version.clone(),
+            scoped: self.scoped,
+            profile: self.profile.clone(),
+            dirs: self.dirs.clone(),
+        }
+    }
+}
+
+/// Returns the access mode of newly created application directories.
+///
+/// Directories which may contain sensitive information are only accessible by the current user.
+fn get_default_mode(kind: Kind) -> Option<u32> {
+    match kind {
+        Kind::Data | Kind::State | Kind::Runtime | Kind::Config => Some(0o700),
+        Kind::Cache | Kind::Documents | Kind::Logs => None,
+    }
+}
+
+/// Returns the given directory and all its parents which don't exist yet, deepest last.
+fn get_missing_dirs(path: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = path
+        .ancestors()
+        .take_while(|v| std::fs::symlink_metadata(v).is_err())
+        .map(PathBuf::from)
+        .collect();
+    dirs.reverse();
+    dirs
+}
+
+/// Hands the given directories to the given (uid, gid) owner, if any.
+fn set_owner(dirs: &[PathBuf], owner: Option<(u32, u32)>) -> std::io::Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            if let Some((uid, gid)) = owner {
+                for dir in dirs {
+                    std::os::unix::fs::chown(dir, Some(uid), Some(gid))?;
+                }
+            }
+            Ok(())
+        } else {
+            // Owners are unsupported on this platform.
+            let _ = (dirs, owner);
+            Ok(())
+        }
+    }
+}
+
+/// Creates a directory with the given access mode, None to use the process umask.
+///
+/// If repair is true and the directory already exists, its access mode is reset to the given
+/// access mode. Created directories, including parents, are handed to the given owner.
+fn create_dir(
+    path: &Path,
+    mode: Option<u32>,
+    repair: bool,
+    owner: Option<(u32, u32)>,
+) -> std::io::Result<()> {
+    let missing = get_missing_dirs(path);
+    create_dir_with_mode(path, mode, repair)?;
+    set_owner(&missing, owner)
+}
+
+fn create_dir_with_mode(path: &Path, mode: Option<u32>, repair: bool) -> std::io::Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+            match mode {
+                Some(mode) if path.is_dir() => {
+                    let permissions = std::fs::metadata(path)?.permissions();
+                    if repair && permissions.mode() & 0o777 != mode {
+                        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+                    }
+                    Ok(())
+                }
+                Some(mode) => std::fs::DirBuilder::new()
+                    .recursive(true)
+                    .mode(mode)
+                    .create(path),
+                None if path.is_dir() => Ok(()),
+                None => std::fs::create_dir_all(path),
+            }
+        } else {
+            // Access modes are unsupported on this platform.
+            let _ = (mode, repair);
+            if !path.is_dir() {
+                std::fs::create_dir_all(path)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Compares two versions component by component, numerically when both components are numbers.
+///
+/// A pre-release (ex: `2.0-beta`) is older than its release and build metadata (ex: `+linux`) is
+/// ignored.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (&str, Option<&str>) {
+        let version = version.split('+').next().unwrap_or(version);
+        match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        }
+    }
+    let ((a, a_pre), (b, b_pre)) = (split(a), split(b));
+    let mut a = a.split('.');
+    let mut b = b.split('.');
+    loop {
+        let ordering = match (a.next(), b.next()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(a), Some(b)) => a.cmp(b),
+    }
+}
+
+/// Turns a channel, version or profile name into a single path component.
+fn get_component(name: &str) -> String {
+    let name = name.trim().replace(std::path::is_separator, "_");
+    match &*name {
+        "" | "." | ".." => "_".into(),
+        _ => name,
+    }
+}
+
+/// Returns the prefix of the environment variables which override the directories of the given
+/// application.
+fn get_env_prefix(name: &str) -> String {
+    name.chars()
+        .map(|v| match v.is_ascii_alphanumeric() {
+            true => v.to_ascii_uppercase(),
+            false => '_',
+        })
+        .collect()
+}
+
+/// Returns the temporary runtime directory of the given (uid, gid) owner, or the current user if
+/// None.
+fn get_runtime_fallback(name: &str, owner: Option<(u32, u32)>) -> PathBuf {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            // The temporary directory may be shared by all users, so make it unique per user.
+            let uid = owner.map(|(uid, _)| uid).unwrap_or_else(|| unsafe { libc::getuid() });
+            std::env::temp_dir().join(format!("{}-{}", name, uid))
+        } else {
+            let _ = owner;
+            std::env::temp_dir().join(name)
+        }
+    }
+}
+
+/// Creates a directory only accessible by the given (uid, gid) owner, or the current user if None.
+///
+/// If the directory already exists, it must be owned by that user and inaccessible to any other
+/// user.
+fn create_private_dir(path: &Path, owner: Option<(u32, u32)>) -> std::io::Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(unix)] {
+            use std::io::ErrorKind;
+            use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+            let missing = get_missing_dirs(path);
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(path)?;
+            set_owner(&missing, owner)?;
+            // Do not follow links: someone else could have placed a link to its own directory.
+            let metadata = std::fs::symlink_metadata(path)?;
+            let uid = owner.map_or_else(|| unsafe { libc::getuid() }, |(uid, _)| uid);
+            if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+                return Err(std::io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    "directory is not private to the current user",
+                ));
+            }
+            Ok(())
+        } else {
+            // Owners are unsupported on this platform.
+            let _ = owner;
+            std::fs::create_dir_all(path)
         }
     }
 }
@@ -225,7 +1120,33 @@ pub fn get_asset(file_name: &str) -> Option<PathBuf> {
 
 #[cfg(test)]
 mod tests {
-    use crate::dirs::App;
+    use crate::dirs::system::{Environment, Paths, Provider};
+    use crate::dirs::{
+        App, AppBuilder, Error, Kind, Operation, Source, PORTABLE_DIR, PORTABLE_MARKER,
+    };
+    use std::path::{Path, PathBuf};
+
+    /// A temporary directory for a single test, removed when dropped even if the test fails.
+    pub struct TempDir(PathBuf);
+
+    impl TempDir {
+        /// Creates a guard for the given test; left-overs of an aborted run are removed first.
+        pub fn new(name: &str) -> TempDir {
+            let path = std::env::temp_dir().join(format!("bp3d-fs-test-{}", name));
+            let _ = std::fs::remove_dir_all(&path);
+            TempDir(path)
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
 
     fn assert_sync_send<T: Sync + Send>(x: T) -> T {
         x
@@ -236,4 +1157,625 @@ mod tests {
         let obj = App::new("test");
         let _ = assert_sync_send(obj);
     }
+
+    struct TestProvider(PathBuf);
+
+    impl Provider for TestProvider {
+        fn get_paths(&self, _: &Environment) -> Paths {
+            Paths {
+                app_data: Some(self.0.join("data")),
+                ..Default::default()
+            }
+        }
+    }
+
+    pub struct StaticProvider(pub Paths);
+
+    impl Provider for StaticProvider {
+        fn get_paths(&self, _: &Environment) -> Paths {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_provider() {
+        let dir = TempDir::new("provider");
+        let root = dir.path();
+        let env = Environment::new().with_var("TEST_CACHE_DIR", root.join("override"));
+        let app = AppBuilder::new("test")
+            .provider(TestProvider(root.into()))
+            .environment(env)
+            .build();
+        assert_eq!(
+            app.get_config().ok(),
+            Some(root.join("data/test/Config").as_ref())
+        );
+        assert_eq!(
+            app.get_source(Kind::Config).ok(),
+            Some(Source::Fallback(Kind::Data))
+        );
+        assert_eq!(app.get_cache().ok(), Some(root.join("override").as_ref()));
+        assert_eq!(app.get_source(Kind::Cache).ok(), Some(Source::Environment));
+    }
+
+    #[test]
+    fn test_service() {
+        let dir = TempDir::new("service");
+        let root = dir.path();
+        let env = Environment::new().with_var("STATE_DIRECTORY", root.join("state"));
+        let app = AppBuilder::new("test")
+            .service(true)
+            .environment(env)
+            .build();
+        assert_eq!(app.get_data().unwrap(), root.join("state"));
+        assert_eq!(app.get_source(Kind::Data).unwrap(), Source::Service);
+        assert_eq!(app.get_documents().unwrap(), root.join("state/Documents"));
+        assert_eq!(
+            app.get_source(Kind::Documents).unwrap(),
+            Source::Fallback(Kind::Data)
+        );
+    }
+
+    #[test]
+    fn test_portable() {
+        let dir = TempDir::new("portable");
+        let root = dir.path();
+        let paths = Paths {
+            app_data: Some(root.join("data")),
+            app_runtime: Some(root.join("runtime")),
+            app_executable_dir: Some(root.join("bin")),
+            ..Default::default()
+        };
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(paths.clone()))
+            .environment(Environment::new())
+            .build();
+        assert!(!app.is_portable());
+        assert_eq!(app.resolve(Kind::Data).unwrap(), root.join("data/test"));
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::write(root.join("bin").join(PORTABLE_MARKER), "").unwrap();
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(paths.clone()))
+            .environment(Environment::new())
+            .build();
+        assert!(app.is_portable());
+        assert_eq!(
+            app.resolve(Kind::Data).unwrap(),
+            root.join("bin").join(PORTABLE_DIR)
+        );
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(paths))
+            .environment(Environment::new())
+            .portable(true)
+            .build();
+        let data = root.join("bin").join(PORTABLE_DIR);
+        assert_eq!(app.get_data().unwrap(), data);
+        assert_eq!(app.get_source(Kind::Data).unwrap(), Source::Portable);
+        assert_eq!(app.resolve(Kind::Cache).unwrap(), data.join("Cache"));
+        assert_eq!(app.resolve(Kind::Config).unwrap(), data.join("Config"));
+        assert_eq!(
+            app.resolve(Kind::Logs).unwrap(),
+            data.join("Documents/Logs")
+        );
+        assert_eq!(
+            app.resolve(Kind::Runtime).unwrap(),
+            root.join("runtime/test")
+        );
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(Paths::default()))
+            .environment(Environment::new())
+            .portable(true)
+            .build();
+        assert!(matches!(app.get_data(), Err(Error::MissingDataDir)));
+    }
+
+    #[test]
+    fn test_with_root() {
+        let dir = TempDir::new("root");
+        let root = dir.path();
+        let app = App::with_root("test", root);
+        assert_eq!(app.get_data().ok(), Some(root.join("data/test").as_ref()));
+        assert_eq!(app.get_cache().ok(), Some(root.join("cache/test").as_ref()));
+        assert_eq!(
+            app.get_config().ok(),
+            Some(root.join("config/test").as_ref())
+        );
+        assert_eq!(
+            app.get_documents().ok(),
+            Some(root.join("data/test/Documents").as_ref())
+        );
+        assert_eq!(
+            app.get_logs().ok(),
+            Some(root.join("state/test/Logs").as_ref())
+        );
+        assert_eq!(
+            app.get_runtime().ok(),
+            Some(root.join("runtime/test").as_ref())
+        );
+        assert!(root.join("state/test/Logs").is_dir());
+        assert_eq!(app.get_source(Kind::Cache).ok(), Some(Source::Root));
+    }
+
+    #[test]
+    fn test_error() {
+        let dir = TempDir::new("error");
+        let root = dir.path();
+        std::fs::create_dir_all(root).unwrap();
+        std::fs::write(root.join("cache"), b"").unwrap();
+        let app = App::with_root("test", root);
+        let err = app.get_cache().unwrap_err();
+        match &err {
+            Error::Io {
+                operation,
+                kind,
+                path,
+                ..
+            } => {
+                assert_eq!(*operation, Operation::Create);
+                assert_eq!(*kind, Kind::Cache);
+                assert_eq!(path, &root.join("cache/test"));
+            }
+            _ => panic!("expected an io error"),
+        }
+        assert!(std::error::Error::source(&err).is_some());
+        assert!(err
+            .to_string()
+            .starts_with("failed to create cache directory"));
+    }
+
+    #[test]
+    fn test_global() {
+        let name = String::from("test");
+        let app = App::with_root(name.as_str(), std::env::temp_dir()).into_owned();
+        drop(name);
+        assert!(App::init_global(app));
+        assert!(!App::init_global(App::new("test")));
+        assert!(App::global().is_some());
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = TempDir::new("resolve");
+        let root = dir.path();
+        let app = App::with_root("test", root);
+        let logs = app.resolve(Kind::Logs).unwrap();
+        assert_eq!(logs, root.join("state/test/Logs"));
+        assert!(!root.exists());
+        app.ensure().unwrap();
+        assert!(logs.is_dir());
+        assert!(root.join("data/test/Documents").is_dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permissions() {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        let dir = TempDir::new("permissions");
+        let root = dir.path();
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o755)
+            .create(root.join("config/test"))
+            .unwrap();
+        let app = AppBuilder::new("test")
+            .root(root)
+            .repair_permissions(true)
+            .build();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(app.get_data().unwrap()), 0o700);
+        assert_eq!(mode(app.get_config().unwrap()), 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_nested_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new("nested_permissions");
+        let root = dir.path();
+        let app = App::with_root("test", root);
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        app.get_documents().unwrap();
+        app.get_logs().unwrap();
+        assert_eq!(mode(&root.join("data/test")), 0o700);
+        assert_eq!(mode(&root.join("state/test")), 0o700);
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+    ))]
+    #[test]
+    fn test_runtime() {
+        use std::os::unix::fs::PermissionsExt;
+        let uid = unsafe { libc::getuid() };
+        let fallback = TempDir::new(&format!("runtime-{}", uid));
+        let xdg = TempDir::new("xdg_runtime");
+        std::fs::create_dir_all(xdg.path()).unwrap();
+        let chmod = |path: &Path, mode| {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap()
+        };
+        // XDG_RUNTIME_DIR is ignored unless it is private to the current user.
+        chmod(xdg.path(), 0o755);
+        let env = Environment::new().with_var("XDG_RUNTIME_DIR", xdg.path());
+        let app = AppBuilder::new("bp3d-fs-test-runtime")
+            .environment(env.clone())
+            .build();
+        assert_eq!(app.resolve(Kind::Runtime).unwrap(), fallback.path());
+        // A pre-existing fallback directory readable by other users is refused.
+        std::fs::create_dir_all(fallback.path()).unwrap();
+        chmod(fallback.path(), 0o755);
+        assert!(matches!(
+            app.get_runtime(),
+            Err(Error::Io {
+                kind: Kind::Runtime,
+                ..
+            })
+        ));
+        chmod(xdg.path(), 0o700);
+        let app = AppBuilder::new("bp3d-fs-test-runtime")
+            .environment(env)
+            .build();
+        assert_eq!(
+            app.get_runtime().unwrap(),
+            xdg.path().join("bp3d-fs-test-runtime")
+        );
+    }
+
+    #[test]
+    fn test_revalidate() {
+        let dir = TempDir::new("revalidate");
+        let root = dir.path();
+        let app = AppBuilder::new("test").root(root).revalidate(true).build();
+        let cache = app.get_cache().unwrap().to_path_buf();
+        std::fs::remove_dir_all(root).unwrap();
+        assert_eq!(app.get_cache().unwrap(), cache);
+        assert!(cache.is_dir());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_revalidate_parent() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new("revalidate_parent");
+        let root = dir.path();
+        let app = AppBuilder::new("test").root(root).revalidate(true).build();
+        let documents = app.get_documents().unwrap().to_path_buf();
+        std::fs::remove_dir_all(root.join("data")).unwrap();
+        assert_eq!(app.get_documents().unwrap(), documents);
+        let mode = std::fs::metadata(root.join("data/test"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let dir = TempDir::new("migrate");
+        let root = dir.path();
+        let legacy = root.join("legacy");
+        std::fs::create_dir_all(legacy.join("plugins")).unwrap();
+        std::fs::write(legacy.join("settings.txt"), "legacy").unwrap();
+        std::fs::write(legacy.join("plugins/a.txt"), "a").unwrap();
+        let app = App::with_root("test", root.join("app"));
+        let config = app.get_config().unwrap();
+        std::fs::create_dir(config.join("plugins")).unwrap();
+        std::fs::write(config.join("plugins/a.txt"), "new").unwrap();
+        let report = app.migrate(Kind::Config, &[&legacy]).unwrap();
+        assert_eq!(
+            report.moved,
+            vec![(legacy.join("settings.txt"), config.join("settings.txt"))]
+        );
+        assert_eq!(report.skipped, vec![legacy.join("plugins/a.txt")]);
+        assert_eq!(std::fs::read(config.join("plugins/a.txt")).unwrap(), b"new");
+        let report = app.migrate(Kind::Config, &[&legacy]).unwrap();
+        assert!(report.moved.is_empty());
+        let report = app.migrate(Kind::Config, &[root]).unwrap();
+        assert!(report.is_empty());
+        assert!(config.join("settings.txt").is_file());
+    }
+
+    #[test]
+    fn test_versions() {
+        let dir = TempDir::new("versions");
+        let root = dir.path();
+        let old = AppBuilder::new("test")
+            .root(root)
+            .channel("beta")
+            .version("1.0")
+            .build();
+        let app = AppBuilder::new("test")
+            .root(root)
+            .channel("beta")
+            .version("1.1")
+            .build();
+        assert_eq!(
+            old.get_cache().unwrap(),
+            root.join("cache/test/beta/Versions/1.0")
+        );
+        assert_eq!(
+            app.get_cache().unwrap(),
+            root.join("cache/test/beta/Versions/1.1")
+        );
+        assert_eq!(
+            app.get_documents().unwrap(),
+            root.join("data/test/Documents")
+        );
+        let new = AppBuilder::new("test")
+            .root(root)
+            .channel("beta")
+            .version("1.10")
+            .build();
+        new.get_cache().unwrap();
+        assert_eq!(app.get_data().unwrap(), root.join("data/test"));
+        assert!(old.list_old_versions(Kind::Cache).unwrap().is_empty());
+        assert_eq!(
+            app.list_old_versions(Kind::Cache).unwrap(),
+            vec![root.join("cache/test/beta/Versions/1.0")]
+        );
+        assert_eq!(app.purge_old_versions(Kind::Cache).unwrap().len(), 1);
+        assert!(new.get_cache().unwrap().is_dir());
+        assert!(app.list_old_versions(Kind::Cache).unwrap().is_empty());
+        assert!(app.get_cache().unwrap().is_dir());
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        use super::compare_versions;
+        use std::cmp::Ordering;
+        assert_eq!(compare_versions("1.9", "1.10"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("2.0-beta", "2.0"), Ordering::Less);
+        assert_eq!(compare_versions("2.0-beta", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.2+linux", "1.2"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_versions_no_channel() {
+        let dir = TempDir::new("versions-no-channel");
+        let root = dir.path();
+        let old = AppBuilder::new("test")
+            .root(root)
+            .version("1.0")
+            .scope(Kind::Data, true)
+            .build();
+        let app = AppBuilder::new("test")
+            .root(root)
+            .version("1.1")
+            .scope(Kind::Data, true)
+            .build();
+        old.ensure().unwrap();
+        assert_eq!(app.get_data().unwrap(), root.join("data/test/Versions/1.1"));
+        let documents = app.get_documents().unwrap();
+        assert_eq!(documents, root.join("data/test/Documents"));
+        std::fs::write(documents.join("save.txt"), "").unwrap();
+        assert_eq!(
+            app.purge_old_versions(Kind::Data).unwrap(),
+            vec![root.join("data/test/Versions/1.0")]
+        );
+        assert!(documents.join("save.txt").is_file());
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dir = TempDir::new("profiles");
+        let root = dir.path();
+        let app = App::with_root("test", root);
+        let player = app.create_profile("player").unwrap();
+        assert_eq!(
+            player.get_data().unwrap(),
+            root.join("data/test/Profiles/player")
+        );
+        assert_eq!(player.get_cache().unwrap(), app.get_cache().unwrap());
+        app.create_profile("tester").unwrap();
+        assert_eq!(app.list_profiles().unwrap(), vec!["player", "tester"]);
+        app.rename_profile("tester", "admin").unwrap();
+        app.delete_profile("player").unwrap();
+        assert_eq!(app.list_profiles().unwrap(), vec!["admin"]);
+        assert!(root.join("config/test/Profiles/admin").is_dir());
+        let is_not_found = |res: Result<(), Error>| match res {
+            Err(Error::Io { error, .. }) => error.kind() == std::io::ErrorKind::NotFound,
+            _ => false,
+        };
+        assert!(is_not_found(app.rename_profile("player", "tester")));
+        assert!(is_not_found(app.delete_profile("player")));
+        assert!(!root.join("data/test/Profiles/tester").exists());
+    }
+
+    #[test]
+    fn test_profiles_conflict() {
+        let dir = TempDir::new("profiles_conflict");
+        let root = dir.path();
+        let app = App::with_root("test", root);
+        assert_eq!(app.list_profiles().unwrap(), Vec::<String>::new());
+        assert!(!root.join("data/test").exists());
+        app.create_profile("player").unwrap();
+        // Only the config directory of "admin" exists: the rename must not split "player".
+        std::fs::create_dir_all(root.join("config/test/Profiles/admin")).unwrap();
+        let err = app.rename_profile("player", "admin").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Io {
+                kind: Kind::Config,
+                ..
+            }
+        ));
+        assert!(root.join("data/test/Profiles/player").is_dir());
+        assert!(root.join("config/test/Profiles/player").is_dir());
+        assert!(!root.join("data/test/Profiles/admin").exists());
+    }
+
+    #[test]
+    fn test_search_paths() {
+        let dir = TempDir::new("search");
+        let root = dir.path();
+        let system = root.join("system");
+        std::fs::create_dir_all(system.join("share/test")).unwrap();
+        std::fs::write(system.join("share/test/default.txt"), "").unwrap();
+        let paths = Paths {
+            app_data: Some(root.join("data")),
+            app_data_dirs: vec![system.join("local"), system.join("share")],
+            ..Default::default()
+        };
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(paths))
+            .environment(Environment::new())
+            .build();
+        assert_eq!(
+            app.get_search_paths(Kind::Data).unwrap(),
+            vec![
+                root.join("data/test"),
+                system.join("local/test"),
+                system.join("share/test")
+            ]
+        );
+        assert_eq!(
+            app.find_data_file("default.txt").unwrap(),
+            Some(system.join("share/test/default.txt"))
+        );
+        std::fs::write(app.get_data().unwrap().join("default.txt"), "").unwrap();
+        assert_eq!(
+            app.find_data_file("default.txt").unwrap(),
+            Some(root.join("data/test/default.txt"))
+        );
+        assert_eq!(app.find_data_file("missing.txt").unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_home_source() {
+        use crate::dirs::system::HomeSource;
+        let env = Environment::current();
+        if std::env::var_os("HOME").is_some_and(|v| !v.is_empty()) {
+            assert_eq!(env.get_home_source(), Some(HomeSource::Environment));
+        }
+        let env = Environment::new().with_home("/home/test");
+        assert_eq!(env.get_home_source(), Some(HomeSource::Custom));
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+    ))]
+    #[test]
+    fn test_ignored_vars() {
+        let env = Environment::new()
+            .with_home("/home/test")
+            .with_var("XDG_CACHE_HOME", "")
+            .with_var("XDG_DATA_HOME", "relative")
+            .with_var("XDG_CONFIG_HOME", "/config")
+            .with_var("XDG_DATA_DIRS", "/usr/share:share");
+        let paths = crate::dirs::system::System.get_paths(&env);
+        assert_eq!(paths.app_cache, Some(PathBuf::from("/home/test/.cache")));
+        assert_eq!(
+            paths.app_data,
+            Some(PathBuf::from("/home/test/.local/share"))
+        );
+        assert_eq!(paths.app_config, Some(PathBuf::from("/config")));
+        assert_eq!(paths.app_data_dirs, vec![PathBuf::from("/usr/share")]);
+        let vars: Vec<_> = env.get_ignored_vars().into_iter().map(|v| v.name).collect();
+        assert_eq!(
+            vars,
+            vec!["XDG_CACHE_HOME", "XDG_DATA_HOME", "XDG_DATA_DIRS"]
+        );
+    }
+
+    #[cfg(all(
+        unix,
+        not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+    ))]
+    #[test]
+    fn test_user_dirs() {
+        let dir = TempDir::new("user_dirs");
+        let root = dir.path();
+        std::fs::create_dir_all(root).unwrap();
+        let content = "XDG_DESKTOP_DIR=\"$HOME/Bureau\"\nXDG_DOWNLOAD_DIR=\"/srv/downloads\"\n";
+        std::fs::write(root.join("user-dirs.dirs"), content).unwrap();
+        let env = Environment::new()
+            .with_home("/home/test")
+            .with_var("XDG_CONFIG_HOME", root)
+            .with_var("XDG_MUSIC_DIR", "/srv/music");
+        let paths = crate::dirs::system::System.get_paths(&env);
+        assert_eq!(paths.user_home, Some(PathBuf::from("/home/test")));
+        assert_eq!(paths.user_desktop, Some(PathBuf::from("/home/test/Bureau")));
+        assert_eq!(paths.user_downloads, Some(PathBuf::from("/srv/downloads")));
+        assert_eq!(paths.user_music, Some(PathBuf::from("/srv/music")));
+        assert_eq!(
+            paths.user_documents,
+            Some(PathBuf::from("/home/test/Documents"))
+        );
+        assert_eq!(
+            paths.user_pictures,
+            Some(PathBuf::from("/home/test/Pictures"))
+        );
+        assert_eq!(paths.user_videos, Some(PathBuf::from("/home/test/Videos")));
+        assert_eq!(
+            paths.user_templates,
+            Some(PathBuf::from("/home/test/Templates"))
+        );
+        assert_eq!(paths.user_public, Some(PathBuf::from("/home/test/Public")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_owner() {
+        use crate::dirs::system::HomeSource;
+        use std::os::unix::fs::MetadataExt;
+        let dir = TempDir::new("owner");
+        let root = dir.path();
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let env = Environment::new()
+            .with_var("SUDO_USER", "nobody")
+            .with_invoking_user();
+        let owner = match env.get_owner() {
+            Some(owner) => {
+                assert_eq!(uid, 0);
+                assert_ne!(owner.0, 0);
+                assert_eq!(env.get_home_source(), Some(HomeSource::Passwd));
+                owner
+            }
+            None => (uid, gid),
+        };
+        let app = AppBuilder::new("test")
+            .root(root)
+            .environment(Environment::new().with_owner(owner.0, owner.1))
+            .build();
+        app.ensure().unwrap();
+        assert_eq!(std::fs::metadata(root.join("data")).unwrap().uid(), owner.0);
+        assert_eq!(
+            std::fs::metadata(app.get_runtime().unwrap()).unwrap().uid(),
+            owner.0
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runtime_owner() {
+        let app = AppBuilder::new("test")
+            .provider(StaticProvider(Paths::default()))
+            .environment(Environment::new().with_owner(4242, 4242))
+            .build();
+        assert_eq!(
+            app.resolve(Kind::Runtime).unwrap(),
+            std::env::temp_dir().join("test-4242")
+        );
+    }
+
+    #[cfg(all(unix, not(any(target_os = "ios", target_os = "android"))))]
+    #[test]
+    fn test_for_user() {
+        let env = Environment::for_user("root").unwrap();
+        let home = env.get_home().unwrap().to_path_buf();
+        assert_eq!(env.get_owner(), Some((0, 0)));
+        assert_eq!(Environment::for_uid(0).unwrap().get_home(), Some(&*home));
+        let paths = crate::dirs::system::System.get_paths(&env);
+        cfg_if::cfg_if! {
+            if #[cfg(target_os = "macos")] {
+                assert_eq!(paths.app_cache, Some(home.join("Library/Caches")));
+            } else {
+                assert_eq!(paths.app_cache, Some(home.join(".cache")));
+            }
+        }
+        assert!(Environment::for_user("bp3d-fs-missing-user").is_none());
+    }
 }
@@ -0,0 +1,216 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Management of application profiles.
+
+use crate::dirs::{get_component, App, Error, Kind, Operation, PROFILES_DIR};
+use std::path::PathBuf;
+
+/// The directories which are specific to each profile.
+pub const KINDS: [Kind; 3] = [Kind::Data, Kind::Config, Kind::Documents];
+
+impl<'a> App<'a> {
+    /// Returns the directory of the given kind containing all profiles.
+    fn get_profiles_dir(&self, kind: Kind) -> Result<PathBuf, Error> {
+        let mut app = self.clone();
+        if app.profile.take().is_some() {
+            app.dirs = Default::default();
+        }
+        Ok(app.resolve(kind)?.join(PROFILES_DIR))
+    }
+
+    /// Returns a handle to the given profile of this application.
+    ///
+    /// The data, config and documents directories of the returned application are nested in a
+    /// profile sub-directory while all other directories are shared between profiles.
+    /// Directories of the profile are created on first use.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the profile.
+    ///
+    /// returns: App
+    pub fn profile(&self, name: &str) -> App<'a> {
+        let mut app = self.clone();
+        app.profile = Some(get_component(name));
+        app.dirs = Default::default();
+        app
+    }
+
+    /// Lists the names of all profiles of this application.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the data directory couldn't be resolved or listed.
+    pub fn list_profiles(&self) -> Result<Vec<String>, Error> {
+        let dir = self.get_profiles_dir(Kind::Data)?;
+        let to_error = |error| Error::Io {
+            operation: Operation::Profile,
+            kind: Kind::Data,
+            path: dir.clone(),
+            error,
+        };
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(v) => v,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(to_error(e)),
+        };
+        let mut profiles = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(to_error)?;
+            if entry.file_type().map_err(to_error)?.is_dir() {
+                profiles.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        profiles.sort();
+        Ok(profiles)
+    }
+
+    /// Creates all directories of the given profile and returns a handle to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the profile.
+    ///
+    /// returns: Result<App, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if some directory couldn't be resolved or created.
+    pub fn create_profile(&self, name: &str) -> Result<App<'a>, Error> {
+        let app = self.profile(name);
+        for kind in KINDS {
+            app.get(kind)?;
+        }
+        Ok(app)
+    }
+
+    /// Renames a profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `from`: the current name of the profile.
+    /// * `to`: the new name of the profile.
+    ///
+    /// returns: Result<(), Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if some directory couldn't be resolved or renamed. The
+    /// rename fails without touching any directory if the profile doesn't exist or a profile with
+    /// the new name already exists and directories which were already renamed are restored if a
+    /// later rename fails.
+    pub fn rename_profile(&self, from: &str, to: &str) -> Result<(), Error> {
+        let mut renames = Vec::with_capacity(KINDS.len());
+        for kind in KINDS {
+            let dir = self.get_profiles_dir(kind)?;
+            let path = dir.join(get_component(from));
+            let target = dir.join(get_component(to));
+            if !path.is_dir() {
+                continue;
+            }
+            if target.exists() {
+                return Err(Error::Io {
+                    operation: Operation::Profile,
+                    kind,
+                    path: target,
+                    error: std::io::ErrorKind::AlreadyExists.into(),
+                });
+            }
+            renames.push((kind, path, target));
+        }
+        if renames.is_empty() {
+            return Err(self.get_not_found_error(from));
+        }
+        for (i, (kind, path, target)) in renames.iter().enumerate() {
+            if let Err(error) = std::fs::rename(path, target) {
+                // Put back the directories which were already renamed so that the profile isn't
+                // left split between both names.
+                for (_, path, target) in renames[..i].iter().rev() {
+                    let _ = std::fs::rename(target, path);
+                }
+                return Err(Error::Io {
+                    operation: Operation::Profile,
+                    kind: *kind,
+                    path: path.clone(),
+                    error,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes a profile and all its files.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the profile to delete.
+    ///
+    /// returns: Result<(), Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Error](self::Error) if the profile doesn't exist or some directory couldn't be
+    /// resolved or deleted.
+    pub fn delete_profile(&self, name: &str) -> Result<(), Error> {
+        let mut paths = Vec::with_capacity(KINDS.len());
+        for kind in KINDS {
+            let path = self.get_profiles_dir(kind)?.join(get_component(name));
+            if path.is_dir() {
+                paths.push((kind, path));
+            }
+        }
+        if paths.is_empty() {
+            return Err(self.get_not_found_error(name));
+        }
+        for (kind, path) in paths {
+            std::fs::remove_dir_all(&path).map_err(|error| Error::Io {
+                operation: Operation::Profile,
+                kind,
+                path,
+                error,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Returns the error reported when the given profile has no directory.
+    fn get_not_found_error(&self, name: &str) -> Error {
+        let kind = KINDS[0];
+        let path = match self.get_profiles_dir(kind) {
+            Ok(dir) => dir.join(get_component(name)),
+            Err(e) => return e,
+        };
+        Error::Io {
+            operation: Operation::Profile,
+            kind,
+            path,
+            error: std::io::ErrorKind::NotFound.into(),
+        }
+    }
+}
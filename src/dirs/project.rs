@@ -0,0 +1,190 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Discovery of project directories.
+//!
+//! A project is the nearest parent of a directory containing a project marker (ex: `.bp3d` or
+//! `project.bpx`), similar to how git finds its repository. The search never crosses the user
+//! home, file system mounts or the file system root.
+
+use crate::dirs::system::Environment;
+use crate::dirs::{Error, Kind, Operation};
+use std::path::{Path, PathBuf};
+
+/// The name of the directory which stores project-scoped files; it also marks the root of a
+/// project.
+pub const PROJECT_DIR: &str = ".bp3d";
+
+/// The name of the project file which marks the root of a project.
+pub const PROJECT_FILE: &str = "project.bpx";
+
+#[cfg(unix)]
+fn get_device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|v| v.dev())
+}
+
+#[cfg(not(unix))]
+fn get_device(_: &Path) -> Option<u64> {
+    None //Mount boundaries are not detected on this platform.
+}
+
+/// Represents a project directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    root: PathBuf,
+    marker: String,
+}
+
+impl Project {
+    /// Finds the nearest project containing the given start path.
+    ///
+    /// See [find_with_markers](self::Project::find_with_markers).
+    pub fn find<P: AsRef<Path>>(start: P) -> Option<Project> {
+        Project::find_with_markers(start, &[PROJECT_DIR, PROJECT_FILE])
+    }
+
+    /// Finds the nearest project containing the given start path.
+    ///
+    /// The search walks up from the start path and stops at the first directory containing any
+    /// of the given markers. The search stops without result at the file system root, the user
+    /// home (which is never considered as a project) or the first directory on a different file
+    /// system than the start path.
+    ///
+    /// # Arguments
+    ///
+    /// * `start`: the path to start searching from.
+    /// * `markers`: the names of files or directories which mark the root of a project.
+    ///
+    /// returns: Option<Project>
+    pub fn find_with_markers<P: AsRef<Path>>(start: P, markers: &[&str]) -> Option<Project> {
+        let start = dunce::canonicalize(start).ok()?;
+        let env = Environment::current();
+        let home = env.get_home().and_then(|v| dunce::canonicalize(v).ok());
+        let device = get_device(&start);
+        for dir in start.ancestors() {
+            if Some(dir) == home.as_deref() || get_device(dir) != device {
+                return None;
+            }
+            if let Some(marker) = markers.iter().find(|v| dir.join(v).exists()) {
+                return Some(Project {
+                    root: dir.into(),
+                    marker: (*marker).into(),
+                });
+            }
+        }
+        None
+    }
+
+    /// Finds the nearest project containing the current working directory.
+    pub fn current() -> Option<Project> {
+        Project::find(std::env::current_dir().ok()?)
+    }
+
+    /// Returns the root directory of this project.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the name of the marker which was found at the root of this project.
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    /// Returns the path to the directory storing the project-scoped files (`<root>/.bp3d`).
+    pub fn get_dir(&self) -> PathBuf {
+        self.root.join(PROJECT_DIR)
+    }
+
+    /// Returns the path to the given project-scoped directory (ex: `<root>/.bp3d/cache`), creating
+    /// it if needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind`: the directory to get.
+    ///
+    /// returns: Result<PathBuf, Error>
+    ///
+    /// # Errors
+    ///
+    /// Returns an [Io](crate::dirs::Error::Io) if the directory couldn't be created.
+    pub fn get(&self, kind: Kind) -> Result<PathBuf, Error> {
+        let path = self.get_dir().join(kind.name());
+        if !path.is_dir() {
+            if let Err(error) = std::fs::create_dir_all(&path) {
+                return Err(Error::Io {
+                    operation: Operation::Create,
+                    kind,
+                    path,
+                    error,
+                });
+            }
+        }
+        Ok(path)
+    }
+
+    /// Returns the path to the project-scoped data directory, creating it if needed.
+    ///
+    /// See [get](self::Project::get).
+    pub fn get_data(&self) -> Result<PathBuf, Error> {
+        self.get(Kind::Data)
+    }
+
+    /// Returns the path to the project-scoped cache directory, creating it if needed.
+    ///
+    /// See [get](self::Project::get).
+    pub fn get_cache(&self) -> Result<PathBuf, Error> {
+        self.get(Kind::Cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Project, PROJECT_DIR, PROJECT_FILE};
+    use crate::dirs::tests::TempDir;
+
+    #[test]
+    fn find() {
+        let dir = TempDir::new("project");
+        let root = dir.path();
+        let nested = root.join("editor/src/ui");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::create_dir_all(root.join("editor").join(PROJECT_DIR)).unwrap();
+        let project = Project::find(&nested).unwrap();
+        assert_eq!(
+            project.root(),
+            dunce::canonicalize(root.join("editor")).unwrap()
+        );
+        assert_eq!(project.marker(), PROJECT_DIR);
+        std::fs::write(root.join("editor/src").join(PROJECT_FILE), "").unwrap();
+        let project = Project::find(&nested).unwrap();
+        assert_eq!(project.marker(), PROJECT_FILE);
+        assert!(project.get_cache().unwrap().ends_with("src/.bp3d/cache"));
+        assert!(Project::find_with_markers(&nested, &["missing"]).is_none());
+    }
+}
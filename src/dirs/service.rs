@@ -0,0 +1,127 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Directories for applications running as a system service.
+//!
+//! Directories are first read from the variables set by systemd (StateDirectory=,
+//! CacheDirectory=, etc) and otherwise fallback to the standard FHS locations. Under windows,
+//! FHS locations are replaced by sub-directories of ProgramData.
+
+use crate::dirs::system::Environment;
+use crate::dirs::Kind;
+use std::path::PathBuf;
+
+#[cfg(windows)]
+fn get_base(env: &Environment, _: &str, sub: &str, name: &str) -> PathBuf {
+    let data = env
+        .get_var("ProgramData")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("C:\\ProgramData"));
+    data.join(name).join(sub)
+}
+
+#[cfg(not(windows))]
+fn get_base(_: &Environment, fhs: &str, _: &str, name: &str) -> PathBuf {
+    PathBuf::from(fhs).join(name)
+}
+
+/// Returns the variable set by systemd, the FHS location and the ProgramData sub-directory of
+/// the given directory or None if the service manager has no such directory.
+fn get_locations(kind: Kind) -> Option<(&'static str, &'static str, &'static str)> {
+    match kind {
+        Kind::Data => Some(("STATE_DIRECTORY", "/var/lib", "")),
+        Kind::Cache => Some(("CACHE_DIRECTORY", "/var/cache", "Cache")),
+        Kind::Logs => Some(("LOGS_DIRECTORY", "/var/log", "Logs")),
+        Kind::Runtime => Some(("RUNTIME_DIRECTORY", "/run", "Runtime")),
+        Kind::Config => Some(("CONFIGURATION_DIRECTORY", "/etc", "Config")),
+        Kind::Documents | Kind::State => None,
+    }
+}
+
+fn get_managed_dir(env: &Environment, var: &str) -> Option<PathBuf> {
+    // When a unit declares multiple directories, systemd joins them with ':'; the first one is
+    // the primary directory.
+    env.get_var(var)
+        .and_then(|v| std::env::split_paths(&v).find(|v| v.is_absolute()))
+}
+
+/// Returns true if the directory of the given kind was created by the service manager, which
+/// then controls its access mode.
+pub fn is_managed(env: &Environment, kind: Kind) -> bool {
+    get_locations(kind).is_some_and(|(var, _, _)| get_managed_dir(env, var).is_some())
+}
+
+/// Returns the directory of the given kind or None if the service manager has no such directory.
+pub fn get_dir(env: &Environment, kind: Kind, name: &str) -> Option<PathBuf> {
+    let (var, fhs, sub) = get_locations(kind)?;
+    let dir = get_managed_dir(env, var).unwrap_or_else(|| get_base(env, fhs, sub, name));
+    Some(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_dir, is_managed};
+    use crate::dirs::system::Environment;
+    use crate::dirs::Kind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn var() {
+        let env = Environment::new().with_var("STATE_DIRECTORY", "/srv/state/test");
+        assert_eq!(
+            get_dir(&env, Kind::Data, "test"),
+            Some(PathBuf::from("/srv/state/test"))
+        );
+        assert_eq!(get_dir(&env, Kind::Documents, "test"), None);
+        assert_eq!(get_dir(&env, Kind::State, "test"), None);
+        assert!(is_managed(&env, Kind::Data));
+        assert!(!is_managed(&env, Kind::Config));
+    }
+
+    #[test]
+    fn multiple() {
+        let root = std::env::temp_dir();
+        let dirs = [PathBuf::from("relative"), root.join("a"), root.join("b")];
+        let value = std::env::join_paths(&dirs).unwrap();
+        let env = Environment::new().with_var("CACHE_DIRECTORY", value);
+        assert_eq!(get_dir(&env, Kind::Cache, "test"), Some(root.join("a")));
+    }
+
+    #[test]
+    fn fallback() {
+        let env = Environment::new().with_var("ProgramData", "C:\\Data");
+        let dir = get_dir(&env, Kind::Logs, "test").unwrap();
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                assert_eq!(dir, PathBuf::from("C:\\Data\\test\\Logs"));
+            } else {
+                assert_eq!(dir, PathBuf::from("/var/log/test"));
+            }
+        }
+    }
+}
@@ -43,9 +43,14 @@ use std::path::PathBuf;
 pub const NS_LIBRARY_DIRECTORY: c_ulong = 5;
 pub const NS_USER_DIRECTORY: c_ulong = 7;
 pub const NS_DOCUMENT_DIRECTORY: c_ulong = 9;
+pub const NS_DESKTOP_DIRECTORY: c_ulong = 12;
 pub const NS_CACHES_DIRECTORY: c_ulong = 13;
 pub const NS_APPLICATION_SUPPORT_DIRECTORY: c_ulong = 14;
 pub const NS_DOWNLOADS_DIRECTORY: c_ulong = 15;
+pub const NS_MOVIES_DIRECTORY: c_ulong = 17;
+pub const NS_MUSIC_DIRECTORY: c_ulong = 18;
+pub const NS_PICTURES_DIRECTORY: c_ulong = 19;
+pub const NS_SHARED_PUBLIC_DIRECTORY: c_ulong = 21;
 
 const NS_USER_DOMAIN_MASK: c_ulong = 1;
 
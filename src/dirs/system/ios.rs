@@ -27,41 +27,84 @@
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::dirs::system::apple_shared::{
-    get_bundled_asset, get_macos_dir, NS_APPLICATION_SUPPORT_DIRECTORY, NS_CACHES_DIRECTORY,
-    NS_DOCUMENT_DIRECTORY, NS_LIBRARY_DIRECTORY,
+    get_bundled_asset, get_exe_path, get_macos_dir, NS_APPLICATION_SUPPORT_DIRECTORY,
+    NS_CACHES_DIRECTORY, NS_DOCUMENT_DIRECTORY, NS_LIBRARY_DIRECTORY,
 };
+use crate::dirs::system::{Environment, IgnoredVar};
 use std::path::PathBuf;
 
-pub fn get_app_cache() -> Option<PathBuf> {
+pub fn get_app_cache(_: &Environment) -> Option<PathBuf> {
     get_macos_dir(NS_CACHES_DIRECTORY).map(PathBuf::from)
 }
 
-pub fn get_app_config() -> Option<PathBuf> {
+pub fn get_app_config(_: &Environment) -> Option<PathBuf> {
     get_macos_dir(NS_LIBRARY_DIRECTORY).map(|path| PathBuf::from(path).join("Preferences"))
 }
 
-pub fn get_app_data() -> Option<PathBuf> {
+pub fn get_app_data(_: &Environment) -> Option<PathBuf> {
     get_macos_dir(NS_APPLICATION_SUPPORT_DIRECTORY).map(PathBuf::from)
 }
 
-pub fn get_app_logs() -> Option<PathBuf> {
+pub fn get_app_data_dirs(_: &Environment) -> Vec<PathBuf> {
+    Vec::new() //Applications are sandboxed under iOS.
+}
+
+pub fn get_app_config_dirs(_: &Environment) -> Vec<PathBuf> {
+    Vec::new() //Applications are sandboxed under iOS.
+}
+
+pub fn get_ignored_vars(_: &Environment) -> Vec<IgnoredVar> {
+    Vec::new() //XDG variables are not used on this platform.
+}
+
+pub fn get_app_logs(_: &Environment) -> Option<PathBuf> {
     None
 }
 
-pub fn get_app_documents() -> Option<PathBuf> {
+pub fn get_app_state(_: &Environment) -> Option<PathBuf> {
+    None
+}
+
+pub fn get_app_runtime(_: &Environment) -> Option<PathBuf> {
+    None
+}
+
+pub fn get_app_documents(_: &Environment) -> Option<PathBuf> {
     get_macos_dir(NS_DOCUMENT_DIRECTORY).map(PathBuf::from)
 }
 
 //On iOS there exists no user directories as all applications are sandboxed
-pub fn get_user_home() -> Option<PathBuf> {
+pub fn get_user_home(_: &Environment) -> Option<PathBuf> {
+    None
+}
+pub fn get_user_documents(_: &Environment) -> Option<PathBuf> {
+    None
+}
+pub fn get_user_downloads(_: &Environment) -> Option<PathBuf> {
+    None
+}
+pub fn get_user_desktop(_: &Environment) -> Option<PathBuf> {
+    None
+}
+pub fn get_user_pictures(_: &Environment) -> Option<PathBuf> {
     None
 }
-pub fn get_user_documents() -> Option<PathBuf> {
+pub fn get_user_music(_: &Environment) -> Option<PathBuf> {
     None
 }
-pub fn get_user_downloads() -> Option<PathBuf> {
+pub fn get_user_videos(_: &Environment) -> Option<PathBuf> {
     None
 }
+pub fn get_user_templates(_: &Environment) -> Option<PathBuf> {
+    None
+}
+pub fn get_user_public(_: &Environment) -> Option<PathBuf> {
+    None
+}
+
+pub fn get_app_executable_dir() -> Option<PathBuf> {
+    get_exe_path()?.parent().map(|v| v.into())
+}
 
 pub fn get_app_bundled_asset(file_name: &str) -> Option<PathBuf> {
     get_bundled_asset(file_name)
@@ -26,31 +26,135 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::dirs::system::{user_dirs, Environment, IgnoredVar};
+use std::ffi::OsStr;
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 
-pub fn get_app_cache() -> Option<PathBuf> {
-    std::env::var_os("XDG_CACHE_HOME")
-        .map(|v| v.into())
-        .or_else(|| std::env::var_os("HOME").map(|v| PathBuf::from(v).join(".cache")))
+/// The variables which must contain an absolute path.
+const VARS: &[&str] = &[
+    "XDG_CACHE_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME",
+    "XDG_STATE_HOME",
+    "XDG_RUNTIME_DIR",
+    "XDG_DOCUMENTS_DIR",
+    "XDG_DOWNLOAD_DIR",
+    "XDG_DESKTOP_DIR",
+    "XDG_PICTURES_DIR",
+    "XDG_MUSIC_DIR",
+    "XDG_VIDEOS_DIR",
+    "XDG_TEMPLATES_DIR",
+    "XDG_PUBLICSHARE_DIR",
+];
+
+/// The variables which contain a list of absolute paths.
+const LIST_VARS: &[&str] = &["XDG_DATA_DIRS", "XDG_CONFIG_DIRS"];
+
+/// Reads a variable which must contain an absolute path.
+///
+/// The XDG base directory spec requires empty values to be treated as unset and relative paths
+/// to be ignored.
+fn get_path_var<K: AsRef<OsStr>>(env: &Environment, var: K) -> Option<PathBuf> {
+    env.get_var(var)
+        .map(PathBuf::from)
+        .filter(|v| v.is_absolute())
+}
+
+/// Looks up a base directory.
+///
+/// The directory is first read from the given variable and otherwise falls back to the given
+/// directory in the user's home.
+fn get_base_dir(env: &Environment, var: &str, fallback: &str) -> Option<PathBuf> {
+    get_path_var(env, var).or_else(|| env.get_home().map(|v| v.join(fallback)))
+}
+
+pub fn get_ignored_vars(env: &Environment) -> Vec<IgnoredVar> {
+    let mut vars: Vec<IgnoredVar> = VARS
+        .iter()
+        .filter_map(|name| {
+            let value = env.get_var(name)?;
+            match Path::new(value).is_absolute() {
+                true => None,
+                false => Some(IgnoredVar::new(name, value)),
+            }
+        })
+        .collect();
+    for name in LIST_VARS {
+        let value = match env.get_var(name) {
+            Some(v) => v,
+            None => continue,
+        };
+        if value.is_empty() {
+            vars.push(IgnoredVar::new(name, value));
+            continue;
+        }
+        vars.extend(
+            std::env::split_paths(value)
+                .filter(|v| !v.is_absolute())
+                .map(|v| IgnoredVar::new(name, v.as_os_str())),
+        );
+    }
+    vars
+}
+
+pub fn get_app_cache(env: &Environment) -> Option<PathBuf> {
+    get_base_dir(env, "XDG_CACHE_HOME", ".cache")
+}
+
+pub fn get_app_config(env: &Environment) -> Option<PathBuf> {
+    get_base_dir(env, "XDG_CONFIG_HOME", ".config")
+}
+
+pub fn get_app_data(env: &Environment) -> Option<PathBuf> {
+    get_base_dir(env, "XDG_DATA_HOME", ".local/share")
+}
+
+pub fn get_app_state(env: &Environment) -> Option<PathBuf> {
+    get_base_dir(env, "XDG_STATE_HOME", ".local/state")
+}
+
+/// Looks up a list of system-wide base directories.
+///
+/// The list is read from the given variable and otherwise falls back to the given default list.
+/// Relative entries are ignored as required by the XDG base directory spec.
+fn get_base_dirs(env: &Environment, var: &str, fallback: &[&str]) -> Vec<PathBuf> {
+    match env.get_var(var).filter(|v| !v.is_empty()) {
+        //Relative entries are ignored, see get_ignored_vars.
+        Some(dirs) => std::env::split_paths(dirs)
+            .filter(|v| v.is_absolute())
+            .collect(),
+        None => fallback.iter().map(PathBuf::from).collect(),
+    }
 }
 
-pub fn get_app_config() -> Option<PathBuf> {
-    std::env::var_os("XDG_CONFIG_HOME")
-        .map(|v| v.into())
-        .or_else(|| std::env::var_os("HOME").map(|v| PathBuf::from(v).join(".config")))
+pub fn get_app_data_dirs(env: &Environment) -> Vec<PathBuf> {
+    get_base_dirs(env, "XDG_DATA_DIRS", &["/usr/local/share", "/usr/share"])
 }
 
-pub fn get_app_data() -> Option<PathBuf> {
-    std::env::var_os("XDG_DATA_HOME")
-        .map(|v| v.into())
-        .or_else(|| std::env::var_os("HOME").map(|v| PathBuf::from(v).join(".local/share")))
+pub fn get_app_config_dirs(env: &Environment) -> Vec<PathBuf> {
+    get_base_dirs(env, "XDG_CONFIG_DIRS", &["/etc/xdg"])
 }
 
-pub fn get_app_logs() -> Option<PathBuf> {
-    None //Per-application logs are unsupported under linux
+pub fn get_app_logs(_: &Environment) -> Option<PathBuf> {
+    None //Per-application logs are unsupported under linux, logs are stored in XDG_STATE_HOME
 }
 
-pub fn get_app_documents() -> Option<PathBuf> {
+pub fn get_app_runtime(env: &Environment) -> Option<PathBuf> {
+    let path = get_path_var(env, "XDG_RUNTIME_DIR")?;
+    let metadata = std::fs::metadata(&path).ok()?;
+    let uid = env
+        .get_owner()
+        .map_or_else(|| unsafe { libc::getuid() }, |(uid, _)| uid);
+    //The XDG spec requires the directory to be owned by the user with access mode 0700.
+    if metadata.is_dir() && metadata.uid() == uid && metadata.mode() & 0o777 == 0o700 {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+pub fn get_app_documents(_: &Environment) -> Option<PathBuf> {
     None //Per-application documents are unsupported under linux
 }
 
@@ -110,6 +214,10 @@ fn get_exe_path() -> Option<PathBuf> {
     }
 }
 
+pub fn get_app_executable_dir() -> Option<PathBuf> {
+    get_exe_path()
+}
+
 pub fn get_app_bundled_asset(file_name: &str) -> Option<PathBuf> {
     //Locate app assets folder.
     let assets = get_exe_path()?.join("Assets");
@@ -118,18 +226,52 @@ pub fn get_app_bundled_asset(file_name: &str) -> Option<PathBuf> {
     Some(file)
 }
 
-pub fn get_user_home() -> Option<PathBuf> {
-    std::env::var_os("HOME").map(|v| v.into())
+pub fn get_user_home(env: &Environment) -> Option<PathBuf> {
+    env.get_home().map(PathBuf::from)
+}
+
+/// Looks up a well-known user directory.
+///
+/// The directory is first read from the environment, then from the user-dirs.dirs file written by
+/// xdg-user-dirs and finally falls back to the given directory in the user's home.
+fn get_user_dir(env: &Environment, name: &str, fallback: &str) -> Option<PathBuf> {
+    get_path_var(env, format!("XDG_{}_DIR", name))
+        .or_else(|| {
+            let home = env.get_home()?;
+            let file = get_app_config(env)?.join("user-dirs.dirs");
+            user_dirs::read(&file, home, name)
+        })
+        .or_else(|| env.get_home().map(|v| v.join(fallback)))
+}
+
+pub fn get_user_documents(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "DOCUMENTS", "Documents")
+}
+
+pub fn get_user_downloads(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "DOWNLOAD", "Downloads")
+}
+
+pub fn get_user_desktop(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "DESKTOP", "Desktop")
+}
+
+pub fn get_user_pictures(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "PICTURES", "Pictures")
+}
+
+pub fn get_user_music(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "MUSIC", "Music")
+}
+
+pub fn get_user_videos(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "VIDEOS", "Videos")
 }
 
-pub fn get_user_documents() -> Option<PathBuf> {
-    std::env::var_os("XDG_DOCUMENTS_DIR")
-        .map(|v| v.into())
-        .or_else(|| std::env::var_os("HOME").map(|v| PathBuf::from(v).join("Documents")))
+pub fn get_user_templates(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "TEMPLATES", "Templates")
 }
 
-pub fn get_user_downloads() -> Option<PathBuf> {
-    std::env::var_os("XDG_DOWNLOAD_DIR")
-        .map(|v| v.into())
-        .or_else(|| std::env::var_os("HOME").map(|v| PathBuf::from(v).join("Downloads")))
+pub fn get_user_public(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, "PUBLICSHARE", "Public")
 }
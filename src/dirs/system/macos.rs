@@ -28,28 +28,82 @@
 
 use crate::dirs::system::apple_shared::{
     get_bundled_asset, get_exe_path, get_macos_dir, get_macos_dir_fail_if_sandbox,
-    NS_APPLICATION_SUPPORT_DIRECTORY, NS_CACHES_DIRECTORY, NS_DOCUMENT_DIRECTORY,
-    NS_DOWNLOADS_DIRECTORY, NS_LIBRARY_DIRECTORY, NS_USER_DIRECTORY,
+    NS_APPLICATION_SUPPORT_DIRECTORY, NS_CACHES_DIRECTORY, NS_DESKTOP_DIRECTORY,
+    NS_DOCUMENT_DIRECTORY, NS_DOWNLOADS_DIRECTORY, NS_LIBRARY_DIRECTORY, NS_MOVIES_DIRECTORY,
+    NS_MUSIC_DIRECTORY, NS_PICTURES_DIRECTORY, NS_SHARED_PUBLIC_DIRECTORY, NS_USER_DIRECTORY,
 };
-use std::path::PathBuf;
+use crate::dirs::system::{Environment, IgnoredVar};
+use std::os::raw::c_ulong;
+use std::path::{Path, PathBuf};
 
-pub fn get_app_cache() -> Option<PathBuf> {
-    get_macos_dir(NS_CACHES_DIRECTORY).map(PathBuf::from)
+/// Returns the home directory of the given environment if it belongs to another user than the
+/// calling process, as Foundation only knows about the directories of the calling process.
+fn get_foreign_home(env: &Environment) -> Option<&Path> {
+    let (uid, _) = env.get_owner()?;
+    if uid == unsafe { libc::getuid() } {
+        return None;
+    }
+    env.get_home()
+}
+
+fn get_dir(env: &Environment, directory: c_ulong, path: &str) -> Option<PathBuf> {
+    match get_foreign_home(env) {
+        Some(home) => Some(home.join(path)),
+        None => get_macos_dir(directory).map(PathBuf::from),
+    }
+}
+
+fn get_user_dir(env: &Environment, directory: c_ulong, path: &str) -> Option<PathBuf> {
+    match get_foreign_home(env) {
+        Some(home) => Some(home.join(path)),
+        None => get_macos_dir_fail_if_sandbox(directory),
+    }
+}
+
+pub fn get_app_cache(env: &Environment) -> Option<PathBuf> {
+    get_dir(env, NS_CACHES_DIRECTORY, "Library/Caches")
 }
 
-pub fn get_app_config() -> Option<PathBuf> {
-    get_macos_dir(NS_LIBRARY_DIRECTORY).map(|path| PathBuf::from(path).join("Preferences"))
+pub fn get_app_config(env: &Environment) -> Option<PathBuf> {
+    get_dir(env, NS_LIBRARY_DIRECTORY, "Library").map(|path| path.join("Preferences"))
 }
 
-pub fn get_app_data() -> Option<PathBuf> {
-    get_macos_dir(NS_APPLICATION_SUPPORT_DIRECTORY).map(PathBuf::from)
+pub fn get_app_data(env: &Environment) -> Option<PathBuf> {
+    get_dir(
+        env,
+        NS_APPLICATION_SUPPORT_DIRECTORY,
+        "Library/Application Support",
+    )
 }
 
-pub fn get_app_logs() -> Option<PathBuf> {
-    get_macos_dir(NS_LIBRARY_DIRECTORY).map(|path| PathBuf::from(path).join("Logs"))
+pub fn get_app_data_dirs(_: &Environment) -> Vec<PathBuf> {
+    vec![PathBuf::from("/Library/Application Support")]
 }
 
-pub fn get_app_documents() -> Option<PathBuf> {
+pub fn get_app_config_dirs(_: &Environment) -> Vec<PathBuf> {
+    vec![PathBuf::from("/Library/Preferences")]
+}
+
+pub fn get_ignored_vars(_: &Environment) -> Vec<IgnoredVar> {
+    Vec::new() //XDG variables are not used on this platform.
+}
+
+pub fn get_app_logs(env: &Environment) -> Option<PathBuf> {
+    get_dir(env, NS_LIBRARY_DIRECTORY, "Library").map(|path| path.join("Logs"))
+}
+
+pub fn get_app_state(_: &Environment) -> Option<PathBuf> {
+    None //There's no dedicated app state folder under macOS.
+}
+
+pub fn get_app_runtime(_: &Environment) -> Option<PathBuf> {
+    None //There's no dedicated app runtime folder under macOS.
+}
+
+pub fn get_app_documents(env: &Environment) -> Option<PathBuf> {
+    if get_foreign_home(env).is_some() {
+        return None; //Other users are never sandboxed.
+    }
     if let Some(dir) = get_macos_dir(NS_DOCUMENT_DIRECTORY) {
         if dir.contains("Library/Containers/") {
             //Running in a sandbox
@@ -62,16 +116,47 @@ pub fn get_app_documents() -> Option<PathBuf> {
     }
 }
 
-pub fn get_user_home() -> Option<PathBuf> {
-    get_macos_dir(NS_USER_DIRECTORY).map(PathBuf::from)
+pub fn get_user_home(env: &Environment) -> Option<PathBuf> {
+    match get_foreign_home(env) {
+        Some(home) => Some(home.into()),
+        None => get_macos_dir(NS_USER_DIRECTORY).map(PathBuf::from),
+    }
+}
+
+pub fn get_user_documents(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_DOCUMENT_DIRECTORY, "Documents")
+}
+
+pub fn get_user_downloads(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_DOWNLOADS_DIRECTORY, "Downloads")
+}
+
+pub fn get_user_desktop(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_DESKTOP_DIRECTORY, "Desktop")
+}
+
+pub fn get_user_pictures(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_PICTURES_DIRECTORY, "Pictures")
+}
+
+pub fn get_user_music(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_MUSIC_DIRECTORY, "Music")
+}
+
+pub fn get_user_videos(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_MOVIES_DIRECTORY, "Movies")
+}
+
+pub fn get_user_templates(_: &Environment) -> Option<PathBuf> {
+    None //There's no templates folder under macOS.
 }
 
-pub fn get_user_documents() -> Option<PathBuf> {
-    get_macos_dir_fail_if_sandbox(NS_DOCUMENT_DIRECTORY)
+pub fn get_user_public(env: &Environment) -> Option<PathBuf> {
+    get_user_dir(env, NS_SHARED_PUBLIC_DIRECTORY, "Public")
 }
 
-pub fn get_user_downloads() -> Option<PathBuf> {
-    get_macos_dir_fail_if_sandbox(NS_DOWNLOADS_DIRECTORY)
+pub fn get_app_executable_dir() -> Option<PathBuf> {
+    get_exe_path()?.parent().map(|v| v.into())
 }
 
 pub fn get_app_bundled_asset(file_name: &str) -> Option<PathBuf> {
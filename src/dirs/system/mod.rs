@@ -43,9 +43,18 @@ mod ios;
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
+#[cfg(unix)]
+mod passwd;
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+mod user_dirs;
 #[cfg(windows)]
 mod windows;
 
+mod provider;
+
 #[cfg(target_os = "ios")]
 use ios as _impl;
 #[cfg(all(
@@ -58,36 +67,113 @@ use macos as _impl;
 #[cfg(windows)]
 use windows as _impl;
 
+pub use provider::{Environment, HomeSource, IgnoredVar, Paths, Provider, System};
+
 pub fn get_app_cache() -> Option<PathBuf> {
-    _impl::get_app_cache()
+    _impl::get_app_cache(&Environment::current())
 }
 
 pub fn get_app_config() -> Option<PathBuf> {
-    _impl::get_app_config()
+    _impl::get_app_config(&Environment::current())
 }
 
 pub fn get_app_data() -> Option<PathBuf> {
-    _impl::get_app_data()
+    _impl::get_app_data(&Environment::current())
+}
+
+/// Returns the list of system-wide base directories to search for application data, in order of
+/// preference (ex: `/usr/local/share` and `/usr/share` on Linux).
+///
+/// On Linux, this is read from XDG_DATA_DIRS.
+pub fn get_app_data_dirs() -> Vec<PathBuf> {
+    _impl::get_app_data_dirs(&Environment::current())
+}
+
+/// Returns the list of system-wide base directories to search for application config, in order
+/// of preference (ex: `/etc/xdg` on Linux).
+///
+/// On Linux, this is read from XDG_CONFIG_DIRS.
+pub fn get_app_config_dirs() -> Vec<PathBuf> {
+    _impl::get_app_config_dirs(&Environment::current())
+}
+
+/// Returns the variables of the current process environment which are ignored because they
+/// are empty or contain relative paths.
+///
+/// See [get_ignored_vars](self::Environment::get_ignored_vars).
+pub fn get_ignored_vars() -> Vec<IgnoredVar> {
+    Environment::current().get_ignored_vars()
 }
 
 pub fn get_app_logs() -> Option<PathBuf> {
-    _impl::get_app_logs()
+    _impl::get_app_logs(&Environment::current())
+}
+
+/// Returns the path to the base directory for persistent application state.
+///
+/// State is data that should persist between application restarts but isn't important or
+/// portable enough to be stored in the application data directory (ex: history, window layout).
+pub fn get_app_state() -> Option<PathBuf> {
+    _impl::get_app_state(&Environment::current())
+}
+
+/// Returns the path to the base directory for runtime files (ex: sockets, pid files).
+///
+/// On Linux, this is only returned if XDG_RUNTIME_DIR is owned by the current user and only
+/// accessible to that user, as required by the XDG base directory spec.
+pub fn get_app_runtime() -> Option<PathBuf> {
+    _impl::get_app_runtime(&Environment::current())
 }
 
 pub fn get_app_documents() -> Option<PathBuf> {
-    _impl::get_app_documents()
+    _impl::get_app_documents(&Environment::current())
 }
 
 pub fn get_user_home() -> Option<PathBuf> {
-    _impl::get_user_home()
+    _impl::get_user_home(&Environment::current())
 }
 
 pub fn get_user_documents() -> Option<PathBuf> {
-    _impl::get_user_documents()
+    _impl::get_user_documents(&Environment::current())
 }
 
 pub fn get_user_downloads() -> Option<PathBuf> {
-    _impl::get_user_downloads()
+    _impl::get_user_downloads(&Environment::current())
+}
+
+pub fn get_user_desktop() -> Option<PathBuf> {
+    _impl::get_user_desktop(&Environment::current())
+}
+
+pub fn get_user_pictures() -> Option<PathBuf> {
+    _impl::get_user_pictures(&Environment::current())
+}
+
+pub fn get_user_music() -> Option<PathBuf> {
+    _impl::get_user_music(&Environment::current())
+}
+
+pub fn get_user_videos() -> Option<PathBuf> {
+    _impl::get_user_videos(&Environment::current())
+}
+
+pub fn get_user_templates() -> Option<PathBuf> {
+    _impl::get_user_templates(&Environment::current())
+}
+
+/// Returns the path to the user's public share directory.
+///
+/// On Windows this is the public folder shared by all users of the machine.
+pub fn get_user_public() -> Option<PathBuf> {
+    _impl::get_user_public(&Environment::current())
+}
+
+/// Returns the path to the directory containing the executable of the application.
+///
+/// Returns None if there is a system issue, ex: the system didn't return a proper path to the current
+/// executing application. This should rarely occur.
+pub fn get_app_executable_dir() -> Option<PathBuf> {
+    _impl::get_app_executable_dir()
 }
 
 /// Returns the path to an asset of the application.
@@ -0,0 +1,120 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Access to the password database of unix systems.
+
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::os::raw::{c_char, c_int};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+/// An entry of the password database.
+pub struct Entry {
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+    pub home: Option<PathBuf>,
+}
+
+fn to_os_string(ptr: *const c_char) -> OsString {
+    if ptr.is_null() {
+        return OsString::new();
+    }
+    let str = unsafe { CStr::from_ptr(ptr) };
+    OsStr::from_bytes(str.to_bytes()).into()
+}
+
+fn lookup<F>(mut f: F) -> Option<Entry>
+where
+    F: FnMut(*mut libc::passwd, &mut [c_char], *mut *mut libc::passwd) -> c_int,
+{
+    let mut size = match unsafe { libc::sysconf(libc::_SC_GETPW_R_SIZE_MAX) } {
+        v if v > 0 => v as usize,
+        _ => 1024,
+    };
+    loop {
+        let mut buffer: Vec<c_char> = vec![0; size];
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        let res = f(&mut pwd, &mut buffer, &mut result);
+        // The buffer is too small to hold the entry.
+        if res == libc::ERANGE && size < 1 << 20 {
+            size *= 2;
+            continue;
+        }
+        if res != 0 || result.is_null() {
+            return None;
+        }
+        let home = PathBuf::from(to_os_string(pwd.pw_dir));
+        return Some(Entry {
+            uid: pwd.pw_uid,
+            gid: pwd.pw_gid,
+            home: Some(home).filter(|v| !v.as_os_str().is_empty()),
+        });
+    }
+}
+
+/// Returns the entry of the given user id or None if the user doesn't exist.
+pub fn get_by_uid(uid: libc::uid_t) -> Option<Entry> {
+    lookup(|pwd, buffer, result| unsafe {
+        libc::getpwuid_r(uid, pwd, buffer.as_mut_ptr(), buffer.len(), result)
+    })
+}
+
+/// Returns the entry of the given user name or None if the user doesn't exist.
+pub fn get_by_name(name: &OsStr) -> Option<Entry> {
+    let name = CString::new(name.as_bytes()).ok()?;
+    lookup(|pwd, buffer, result| unsafe {
+        libc::getpwnam_r(
+            name.as_ptr(),
+            pwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            result,
+        )
+    })
+}
+
+/// Returns the entry of the current user.
+pub fn get_current() -> Option<Entry> {
+    get_by_uid(unsafe { libc::getuid() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_by_name, get_by_uid};
+    use std::ffi::OsStr;
+    use std::path::Path;
+
+    #[test]
+    fn root() {
+        let entry = get_by_uid(0).unwrap();
+        assert!(entry.home.as_deref().is_some_and(Path::is_absolute));
+        assert_eq!(get_by_name(OsStr::new("root")).unwrap().uid, 0);
+        assert!(get_by_name(OsStr::new("bp3d-fs-missing-user")).is_none());
+    }
+}
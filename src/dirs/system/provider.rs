@@ -0,0 +1,357 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use super::_impl;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+/// The variables which locate the per-user base directories and are dropped when switching user.
+#[cfg(all(unix, not(target_os = "ios")))]
+const XDG_USER_VARS: [&str; 4] = [
+    "XDG_CONFIG_HOME",
+    "XDG_DATA_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_STATE_HOME",
+];
+
+/// Describes where the home directory of an [Environment](self::Environment) was found.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HomeSource {
+    /// The home directory was read from the HOME (USERPROFILE under windows) variable.
+    Environment,
+
+    /// The home directory was read from the password database because HOME is unset.
+    Passwd,
+
+    /// The home directory was set with [with_home](self::Environment::with_home).
+    Custom,
+}
+
+/// A variable which is ignored while resolving system directories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IgnoredVar {
+    /// The name of the variable.
+    pub name: &'static str,
+
+    /// The ignored value; for variables containing a list of paths, this is only the ignored
+    /// entry.
+    pub value: OsString,
+}
+
+impl IgnoredVar {
+    #[allow(dead_code)] // Unused on platforms which don't read any XDG variable.
+    pub(crate) fn new(name: &'static str, value: &OsStr) -> IgnoredVar {
+        IgnoredVar {
+            name,
+            value: value.into(),
+        }
+    }
+}
+
+/// A snapshot of the environment used to resolve system directories.
+#[derive(Clone, Debug, Default)]
+pub struct Environment {
+    vars: HashMap<OsString, OsString>,
+    home: Option<(PathBuf, HomeSource)>,
+    owner: Option<(u32, u32)>,
+}
+
+impl Environment {
+    /// Creates an empty environment with no variables and no home directory.
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    /// Creates a snapshot of the environment of the current process.
+    ///
+    /// On unix platforms, the home directory falls back to the password database entry of the
+    /// current user when HOME is unset or empty.
+    pub fn current() -> Environment {
+        let vars: HashMap<OsString, OsString> = std::env::vars_os().collect();
+        let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+        let home = vars
+            .get(OsStr::new(var))
+            .filter(|v| !v.is_empty())
+            .map(|v| (PathBuf::from(v), HomeSource::Environment));
+        #[cfg(unix)]
+        let home = home.or_else(|| {
+            let home = super::passwd::get_current()?.home?;
+            Some((home, HomeSource::Passwd))
+        });
+        Environment {
+            vars,
+            home,
+            owner: None,
+        }
+    }
+
+    /// Creates the environment of the given user from the password database.
+    ///
+    /// The environment has no variables, so system directories resolve to their defaults in the
+    /// home directory of the user (on Linux, this includes the user-dirs.dirs file of the user),
+    /// and directories created by [App](crate::dirs::App) are handed to that user. Use
+    /// [AppBuilder::environment](crate::dirs::AppBuilder::environment) to resolve the application
+    /// directories of that user.
+    ///
+    /// Returns None if the user doesn't exist, on non-unix platforms or on iOS where applications
+    /// only have access to their own container. On macOS, the directories of a user other than the
+    /// current one are derived from its home directory (`<home>/Library/...`).
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: the name of the user.
+    ///
+    /// returns: Option<Environment>
+    pub fn for_user<S: AsRef<OsStr>>(name: S) -> Option<Environment> {
+        cfg_if::cfg_if! {
+            if #[cfg(all(unix, not(target_os = "ios")))] {
+                let entry = super::passwd::get_by_name(name.as_ref())?;
+                Some(Environment::new().with_user(entry))
+            } else {
+                let _ = name;
+                None
+            }
+        }
+    }
+
+    /// Creates the environment of the given user id from the password database.
+    ///
+    /// See [for_user](self::Environment::for_user).
+    pub fn for_uid(uid: u32) -> Option<Environment> {
+        cfg_if::cfg_if! {
+            if #[cfg(all(unix, not(target_os = "ios")))] {
+                let entry = super::passwd::get_by_uid(uid)?;
+                Some(Environment::new().with_user(entry))
+            } else {
+                let _ = uid;
+                None
+            }
+        }
+    }
+
+    /// Creates a snapshot of the environment of the user who invoked sudo or pkexec.
+    ///
+    /// See [with_invoking_user](self::Environment::with_invoking_user).
+    pub fn invoking_user() -> Environment {
+        Environment::current().with_invoking_user()
+    }
+
+    /// Switches this environment to the user who invoked sudo or pkexec.
+    ///
+    /// If the process runs as root and SUDO_UID, SUDO_USER or PKEXEC_UID designates another
+    /// user, the home directory is replaced by the home directory of that user read from the
+    /// password database, the XDG base directory variables of the invoking process are dropped
+    /// and directories created by [App](crate::dirs::App) are handed to that user. Otherwise,
+    /// this environment is returned unchanged. This has no effect on non-unix platforms and on
+    /// iOS.
+    pub fn with_invoking_user(self) -> Self {
+        cfg_if::cfg_if! {
+            if #[cfg(all(unix, not(target_os = "ios")))] {
+                use super::passwd;
+                if unsafe { libc::geteuid() } != 0 {
+                    return self;
+                }
+                let get_uid = |var| {
+                    let uid = self.get_var(var)?.to_str()?.parse().ok()?;
+                    passwd::get_by_uid(uid)
+                };
+                let entry = get_uid("SUDO_UID")
+                    .or_else(|| passwd::get_by_name(self.get_var("SUDO_USER")?))
+                    .or_else(|| get_uid("PKEXEC_UID"))
+                    .filter(|v| v.uid != 0);
+                match entry {
+                    Some(entry) => self.with_user(entry),
+                    None => self,
+                }
+            } else {
+                self
+            }
+        }
+    }
+
+    #[cfg(all(unix, not(target_os = "ios")))]
+    fn with_user(mut self, entry: super::passwd::Entry) -> Self {
+        // The base directories of the previous user would otherwise take precedence over the
+        // home of the new user.
+        for var in XDG_USER_VARS {
+            self.vars.remove(OsStr::new(var));
+        }
+        match entry.home {
+            Some(home) => {
+                self.vars.insert("HOME".into(), home.clone().into());
+                self.home = Some((home, HomeSource::Passwd));
+            }
+            None => {
+                self.vars.remove(OsStr::new("HOME"));
+                self.home = None;
+            }
+        }
+        self.owner = Some((entry.uid, entry.gid));
+        self
+    }
+
+    /// Sets a variable in this environment.
+    pub fn with_var<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Self {
+        self.vars.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the home directory of this environment.
+    pub fn with_home<P: Into<PathBuf>>(mut self, home: P) -> Self {
+        self.home = Some((home.into(), HomeSource::Custom));
+        self
+    }
+
+    /// Returns the value of a variable in this environment.
+    pub fn get_var<K: AsRef<OsStr>>(&self, key: K) -> Option<&OsStr> {
+        self.vars.get(key.as_ref()).map(|v| v.as_os_str())
+    }
+
+    /// Returns the home directory of this environment.
+    pub fn get_home(&self) -> Option<&Path> {
+        self.home.as_ref().map(|(v, _)| v.as_path())
+    }
+
+    /// Returns the variables of this environment which are ignored while resolving system
+    /// directories.
+    ///
+    /// On Linux, the XDG base directory spec requires variables containing an empty value or a
+    /// relative path to be ignored, in which case the documented default is used.
+    pub fn get_ignored_vars(&self) -> Vec<IgnoredVar> {
+        _impl::get_ignored_vars(self)
+    }
+
+    /// Sets the (uid, gid) owner of the directories created in this environment.
+    ///
+    /// This has no effect on non-unix platforms.
+    pub fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.owner = Some((uid, gid));
+        self
+    }
+
+    /// Returns the (uid, gid) owner of the directories created in this environment or None if
+    /// directories are owned by the current user.
+    pub fn get_owner(&self) -> Option<(u32, u32)> {
+        self.owner
+    }
+
+    /// Returns where the home directory of this environment was found or None if this
+    /// environment has no home directory.
+    pub fn get_home_source(&self) -> Option<HomeSource> {
+        self.home.as_ref().map(|(_, v)| *v)
+    }
+}
+
+/// Represents all well-known system directories.
+///
+/// Unsupported directories are None.
+#[derive(Clone, Debug, Default)]
+pub struct Paths {
+    pub app_cache: Option<PathBuf>,
+    pub app_config: Option<PathBuf>,
+    pub app_config_dirs: Vec<PathBuf>,
+    pub app_data: Option<PathBuf>,
+    pub app_data_dirs: Vec<PathBuf>,
+    pub app_logs: Option<PathBuf>,
+    pub app_state: Option<PathBuf>,
+    pub app_runtime: Option<PathBuf>,
+    pub app_documents: Option<PathBuf>,
+    pub app_executable_dir: Option<PathBuf>,
+    pub user_home: Option<PathBuf>,
+    pub user_documents: Option<PathBuf>,
+    pub user_downloads: Option<PathBuf>,
+    pub user_desktop: Option<PathBuf>,
+    pub user_pictures: Option<PathBuf>,
+    pub user_music: Option<PathBuf>,
+    pub user_videos: Option<PathBuf>,
+    pub user_templates: Option<PathBuf>,
+    pub user_public: Option<PathBuf>,
+}
+
+/// Provides the system directories for a given environment.
+///
+/// Implement this trait to replace the system directories seen by an
+/// [App](crate::dirs::App), ex: in tests.
+pub trait Provider: Send + Sync {
+    /// Returns all well-known system directories for the given environment.
+    fn get_paths(&self, env: &Environment) -> Paths;
+}
+
+/// The provider of the current platform.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct System;
+
+impl Provider for System {
+    fn get_paths(&self, env: &Environment) -> Paths {
+        Paths {
+            app_cache: _impl::get_app_cache(env),
+            app_config: _impl::get_app_config(env),
+            app_config_dirs: _impl::get_app_config_dirs(env),
+            app_data: _impl::get_app_data(env),
+            app_data_dirs: _impl::get_app_data_dirs(env),
+            app_logs: _impl::get_app_logs(env),
+            app_state: _impl::get_app_state(env),
+            app_runtime: _impl::get_app_runtime(env),
+            app_documents: _impl::get_app_documents(env),
+            app_executable_dir: _impl::get_app_executable_dir(),
+            user_home: _impl::get_user_home(env),
+            user_documents: _impl::get_user_documents(env),
+            user_downloads: _impl::get_user_downloads(env),
+            user_desktop: _impl::get_user_desktop(env),
+            user_pictures: _impl::get_user_pictures(env),
+            user_music: _impl::get_user_music(env),
+            user_videos: _impl::get_user_videos(env),
+            user_templates: _impl::get_user_templates(env),
+            user_public: _impl::get_user_public(env),
+        }
+    }
+}
+
+#[cfg(all(test, unix, not(target_os = "ios")))]
+mod tests {
+    use super::{Environment, HomeSource};
+    use crate::dirs::system::passwd;
+
+    #[test]
+    fn with_user() {
+        let entry = passwd::get_by_uid(0).unwrap();
+        let home = entry.home.clone().unwrap();
+        let env = Environment::new()
+            .with_var("XDG_CONFIG_HOME", "/home/other/.config")
+            .with_var("XDG_STATE_HOME", "/home/other/.local/state")
+            .with_var("LANG", "C")
+            .with_user(entry);
+        assert_eq!(env.get_var("XDG_CONFIG_HOME"), None);
+        assert_eq!(env.get_var("XDG_STATE_HOME"), None);
+        assert_eq!(env.get_var("LANG"), Some("C".as_ref()));
+        assert_eq!(env.get_home(), Some(&*home));
+        assert_eq!(env.get_home_source(), Some(HomeSource::Passwd));
+        assert_eq!(env.get_owner(), Some((0, 0)));
+    }
+}
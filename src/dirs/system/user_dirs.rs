@@ -0,0 +1,173 @@
+// Copyright (c) 2022, BlockProject 3D
+//
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without modification,
+// are permitted provided that the following conditions are met:
+//
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright notice,
+//       this list of conditions and the following disclaimer in the documentation
+//       and/or other materials provided with the distribution.
+//     * Neither the name of BlockProject 3D nor the names of its contributors
+//       may be used to endorse or promote products derived from this software
+//       without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS
+// "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT
+// LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR
+// A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT OWNER OR
+// CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL,
+// EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+// PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR
+// PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF
+// LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING
+// NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+// SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Parser for the xdg-user-dirs configuration file (user-dirs.dirs).
+//!
+//! The file is written by xdg-user-dirs-update as a list of shell assignments, ex:
+//! `XDG_DOWNLOAD_DIR="$HOME/Téléchargements"`. Only the subset of the shell syntax defined by
+//! xdg-user-dirs is supported: values must be double quoted and either start with `$HOME` or be
+//! absolute paths; a backslash escapes the next character.
+
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Parses a single line of a user-dirs.dirs file.
+///
+/// Returns the name of the directory (ex: DOWNLOAD for XDG_DOWNLOAD_DIR) and its value or None
+/// if the line is a comment, empty or invalid.
+fn parse_line<'a>(line: &'a [u8], home: &Path) -> Option<(&'a [u8], PathBuf)> {
+    let line = trim_start(line);
+    if line.first() == Some(&b'#') {
+        return None;
+    }
+    let line = line.strip_prefix(b"XDG_")?;
+    let end = line.iter().position(|v| *v == b'=')?;
+    let name = trim_end(&line[..end]).strip_suffix(b"_DIR")?;
+    let value = trim_start(&line[end + 1..]).strip_prefix(b"\"")?;
+    let (mut path, value) = match value.strip_prefix(b"$HOME") {
+        Some(v) if v.first() == Some(&b'/') || v.first() == Some(&b'"') => {
+            (home.to_path_buf(), v.strip_prefix(b"/").unwrap_or(v))
+        }
+        Some(_) => return None,
+        None if value.first() == Some(&b'/') => (PathBuf::new(), value),
+        None => return None,
+    };
+    let mut buffer = Vec::with_capacity(value.len());
+    let mut iter = value.iter();
+    loop {
+        match iter.next()? {
+            b'"' => break,
+            b'\\' => buffer.push(*iter.next()?),
+            v => buffer.push(*v),
+        }
+    }
+    if !buffer.is_empty() {
+        path.push(OsStr::from_bytes(&buffer));
+    }
+    Some((name, path))
+}
+
+fn trim_start(line: &[u8]) -> &[u8] {
+    let pos = line
+        .iter()
+        .position(|v| !v.is_ascii_whitespace())
+        .unwrap_or(line.len());
+    &line[pos..]
+}
+
+fn trim_end(line: &[u8]) -> &[u8] {
+    let pos = line
+        .iter()
+        .rposition(|v| !v.is_ascii_whitespace())
+        .map(|v| v + 1)
+        .unwrap_or(0);
+    &line[..pos]
+}
+
+/// Looks up a directory in the content of a user-dirs.dirs file.
+///
+/// # Arguments
+///
+/// * `content`: the content of the user-dirs.dirs file.
+/// * `home`: the path to the user's home directory used to expand `$HOME`.
+/// * `name`: the name of the directory to look for (ex: DOWNLOAD for XDG_DOWNLOAD_DIR).
+///
+/// returns: Option<PathBuf>
+pub fn parse(content: &[u8], home: &Path, name: &str) -> Option<PathBuf> {
+    // The file is meant to be sourced by a shell, so the last assignment wins.
+    content
+        .split(|v| *v == b'\n')
+        .rev()
+        .filter_map(|line| parse_line(line, home))
+        .find(|(v, _)| *v == name.as_bytes())
+        .map(|(_, path)| path)
+}
+
+/// Reads a directory from the given user-dirs.dirs file.
+///
+/// Returns None if the file does not exist or doesn't define the requested directory.
+pub fn read(file: &Path, home: &Path, name: &str) -> Option<PathBuf> {
+    let content = std::fs::read(file).ok()?;
+    parse(&content, home, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use std::path::Path;
+
+    const HOME: &str = "/home/user";
+
+    const FILE: &str = r#"# This file is written by xdg-user-dirs-update
+# If you want to change or add directories, just edit the line you're
+# interested in. All local changes will be retained on the next run.
+# Format is XDG_xxx_DIR="$HOME/yyy", where yyy is a shell-escaped
+# homedir-relative path, or XDG_xxx_DIR="/yyy", where /yyy is an
+# absolute path. No other format is supported.
+#
+XDG_DESKTOP_DIR="$HOME/Bureau"
+XDG_DOWNLOAD_DIR="$HOME/Téléchargements"
+XDG_TEMPLATES_DIR="$HOME/"
+XDG_PUBLICSHARE_DIR="/srv/public"
+  XDG_DOCUMENTS_DIR =  "$HOME/My \"Documents\""
+XDG_MUSIC_DIR=$HOME/Music
+XDG_PICTURES_DIR="$HOMEPictures"
+XDG_VIDEOS_DIR="Videos"
+"#;
+
+    fn get(name: &str) -> Option<String> {
+        parse(FILE.as_bytes(), Path::new(HOME), name).map(|v| v.to_string_lossy().into_owned())
+    }
+
+    #[test]
+    fn basic() {
+        assert_eq!(get("DESKTOP").as_deref(), Some("/home/user/Bureau"));
+        assert_eq!(
+            get("DOWNLOAD").as_deref(),
+            Some("/home/user/Téléchargements")
+        );
+        assert_eq!(get("TEMPLATES").as_deref(), Some("/home/user"));
+        assert_eq!(get("PUBLICSHARE").as_deref(), Some("/srv/public"));
+        assert_eq!(
+            get("DOCUMENTS").as_deref(),
+            Some("/home/user/My \"Documents\"")
+        );
+        let content = b"XDG_DESKTOP_DIR=\"$HOME/A\"\nXDG_DESKTOP_DIR=\"$HOME/B\"\n";
+        let path = parse(content, Path::new(HOME), "DESKTOP");
+        assert_eq!(path.as_deref(), Some(Path::new("/home/user/B")));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(get("MUSIC"), None);
+        assert_eq!(get("PICTURES"), None);
+        assert_eq!(get("VIDEOS"), None);
+        assert_eq!(get("UNKNOWN"), None);
+    }
+}
@@ -26,6 +26,7 @@
 // NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
 // SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use crate::dirs::system::{Environment, IgnoredVar};
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
 use std::path::PathBuf;
@@ -36,8 +37,9 @@ use windows_sys::Win32::Foundation::{
 use windows_sys::Win32::System::Com::CoTaskMemFree;
 use windows_sys::Win32::System::LibraryLoader::GetModuleFileNameW;
 use windows_sys::Win32::UI::Shell::{
-    FOLDERID_Documents, FOLDERID_Downloads, FOLDERID_LocalAppData, FOLDERID_Profile,
-    FOLDERID_RoamingAppData, SHGetKnownFolderPath,
+    FOLDERID_Desktop, FOLDERID_Documents, FOLDERID_Downloads, FOLDERID_LocalAppData,
+    FOLDERID_Music, FOLDERID_Pictures, FOLDERID_Profile, FOLDERID_Public, FOLDERID_RoamingAppData,
+    FOLDERID_Templates, FOLDERID_Videos, SHGetKnownFolderPath,
 };
 
 fn get_windows_path(folder: GUID) -> Option<PathBuf> {
@@ -59,23 +61,46 @@ fn get_windows_path(folder: GUID) -> Option<PathBuf> {
     }
 }
 
-pub fn get_app_cache() -> Option<PathBuf> {
+pub fn get_app_cache(_: &Environment) -> Option<PathBuf> {
     get_windows_path(FOLDERID_LocalAppData)
 }
 
-pub fn get_app_config() -> Option<PathBuf> {
+pub fn get_app_config(_: &Environment) -> Option<PathBuf> {
     None //There's no dedicated app config folder under windows.
 }
 
-pub fn get_app_data() -> Option<PathBuf> {
+pub fn get_app_data(_: &Environment) -> Option<PathBuf> {
     get_windows_path(FOLDERID_RoamingAppData)
 }
 
-pub fn get_app_logs() -> Option<PathBuf> {
+pub fn get_app_data_dirs(env: &Environment) -> Vec<PathBuf> {
+    env.get_var("ProgramData")
+        .map(PathBuf::from)
+        .into_iter()
+        .collect()
+}
+
+pub fn get_app_config_dirs(_: &Environment) -> Vec<PathBuf> {
+    Vec::new() //There's no dedicated app config folder under windows.
+}
+
+pub fn get_ignored_vars(_: &Environment) -> Vec<IgnoredVar> {
+    Vec::new() //XDG variables are not used on this platform.
+}
+
+pub fn get_app_logs(_: &Environment) -> Option<PathBuf> {
     None //There's no dedicated app logs folder under windows.
 }
 
-pub fn get_app_documents() -> Option<PathBuf> {
+pub fn get_app_state(_: &Environment) -> Option<PathBuf> {
+    None //There's no dedicated app state folder under windows.
+}
+
+pub fn get_app_runtime(_: &Environment) -> Option<PathBuf> {
+    None //There's no dedicated app runtime folder under windows.
+}
+
+pub fn get_app_documents(_: &Environment) -> Option<PathBuf> {
     None //There's no dedicated app documents (public files) folder under windows.
 }
 
@@ -118,22 +143,50 @@ fn get_exe_path() -> Option<PathBuf> {
     }
 }
 
+pub fn get_app_executable_dir() -> Option<PathBuf> {
+    get_exe_path()?.parent().map(|v| v.into())
+}
+
 pub fn get_app_bundled_asset(file_name: &str) -> Option<PathBuf> {
     //Locate app assets folder.
-    let assets = get_exe_path()?.parent()?.join("Assets");
+    let assets = get_app_executable_dir()?.join("Assets");
     //Concat with file_name.
     let file = assets.join(file_name);
     Some(file)
 }
 
-pub fn get_user_home() -> Option<PathBuf> {
+pub fn get_user_home(_: &Environment) -> Option<PathBuf> {
     get_windows_path(FOLDERID_Profile)
 }
 
-pub fn get_user_documents() -> Option<PathBuf> {
+pub fn get_user_documents(_: &Environment) -> Option<PathBuf> {
     get_windows_path(FOLDERID_Documents)
 }
 
-pub fn get_user_downloads() -> Option<PathBuf> {
+pub fn get_user_downloads(_: &Environment) -> Option<PathBuf> {
     get_windows_path(FOLDERID_Downloads)
 }
+
+pub fn get_user_desktop(_: &Environment) -> Option<PathBuf> {
+    get_windows_path(FOLDERID_Desktop)
+}
+
+pub fn get_user_pictures(_: &Environment) -> Option<PathBuf> {
+    get_windows_path(FOLDERID_Pictures)
+}
+
+pub fn get_user_music(_: &Environment) -> Option<PathBuf> {
+    get_windows_path(FOLDERID_Music)
+}
+
+pub fn get_user_videos(_: &Environment) -> Option<PathBuf> {
+    get_windows_path(FOLDERID_Videos)
+}
+
+pub fn get_user_templates(_: &Environment) -> Option<PathBuf> {
+    get_windows_path(FOLDERID_Templates)
+}
+
+pub fn get_user_public(_: &Environment) -> Option<PathBuf> {
+    get_windows_path(FOLDERID_Public)
+}